@@ -3,11 +3,15 @@ use core::fmt::{Display, Formatter, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{BufferSize, Device, OutputCallbackInfo, SampleRate, StreamConfig};
 use owo_colors::{OwoColorize, Rgb};
-use std::cmp::Ordering;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
 use std::f32::consts::PI;
-use std::io;
-use std::sync::Arc;
-use std::time::Duration;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tokio;
 
 #[tokio::main]
@@ -26,59 +30,22 @@ struct Instrument {
     fret_count: usize,
     tuning: Vec<NotePitch>,
     fretboard: Vec<Vec<NoteDisplay>>,
+    layers: Vec<HighlightLayer>,
+    user_layers: Vec<HighlightLayer>,
+    scale_length: ScaleLength,
+    string_gauges: Vec<StringGauge>,
 }
 
 impl Display for Instrument {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let white_rgb = Rgb {
-            0: 255,
-            1: 255,
-            2: 255,
-        };
-        let marked_frets: Vec<usize> = vec![1, 3, 5, 7, 9, 12, 15, 17, 19, 21, 24];
-        let mut marked = false;
-        for i in (0..self.string_count).rev() {
-            write!(f, "{} ", (self.string_count - i).color(white_rgb))?;
-            for j in 0..(self.fret_count + 1) {
-                marked = false;
-                for mark in &marked_frets {
-                    if j == *mark {
-                        marked = true;
-                    }
-                }
-                match marked {
-                    false => {
-                        match &self.fretboard[i][j].note_pitch.note_name.accidental {
-                            None => write!(f, "{} ", self.fretboard[i][j])?,
-                            Some(_accidental) => write!(f, "{}", self.fretboard[i][j])?,
-                        };
-                    }
-                    true => {
-                        match &self.fretboard[i][j].note_pitch.note_name.accidental {
-                            None => write!(f, "\x1b[4m{} \x1b[0m", self.fretboard[i][j])?,
-                            Some(_accidental) => {
-                                write!(f, "\x1b[4m{}\x1b[0m", self.fretboard[i][j])?
-                            }
-                        };
-                    }
-                }
-                write!(f, " ")?;
-            }
-            write!(f, "\n")?;
-        }
-        write!(f, "  ")?;
-        for i in 0..(self.fret_count + 1) {
-            match i {
-                0..=9 => write!(f, "{}   ", i.color(white_rgb))?,
-                10.. => write!(f, "{}  ", i.color(white_rgb))?,
-                _ => panic!("unexpected fret_count"),
-            };
-        }
-        write!(f, "\n")?;
-        Ok(())
+        let options = RenderOptions::new();
+        let view = FretboardView::new(self, &options, &self.root_note.note_name, None);
+        write!(f, "{}", view)
     }
 }
 
+const MAX_FRETS: usize = 36;
+
 impl Instrument {
     fn new(
         instrument_type: &InstrumentType,
@@ -95,21 +62,127 @@ impl Instrument {
             fret_count: fret_count,
             tuning: Vec::new(),
             fretboard: Vec::new(),
+            layers: Vec::new(),
+            user_layers: Vec::new(),
+            scale_length: ScaleLength::new(instrument_type),
+            string_gauges: Vec::new(),
         };
         Instrument::calculate_tuning(&mut instrument);
         Instrument::calculate_notes(&mut instrument);
+        instrument.show_all();
+        instrument.string_gauges = StringGauge::default_set(&instrument);
+        instrument
+    }
+
+    // An instrument with any tuning, as for custom instruments, rather than one worked out from
+    // a root note and tuning type.
+    fn from_tuning(
+        instrument_type: &InstrumentType,
+        tuning_type: &TuningType,
+        tuning: &[NotePitch],
+        fret_count: usize,
+    ) -> Self {
+        let mut instrument = Instrument {
+            instrument_type: instrument_type.clone(),
+            tuning_type: tuning_type.clone(),
+            root_note: tuning[0].clone(),
+            string_count: tuning.len(),
+            fret_count,
+            tuning: tuning.to_vec(),
+            fretboard: Vec::new(),
+            layers: Vec::new(),
+            user_layers: Vec::new(),
+            scale_length: ScaleLength::new(instrument_type),
+            string_gauges: Vec::new(),
+        };
+        instrument.calculate_notes();
+        instrument.show_all();
+        instrument.string_gauges = StringGauge::default_set(&instrument);
         instrument
     }
 
+    // The usual tuning for each type in string order across the neck, lowest string first. The
+    // banjo and ukulele are re-entrant: the banjo's short fifth string (G4 drone) and the
+    // ukulele's G4 sit where a low string would, and the banjo's is treated as full length.
+    fn standard(instrument_type: &InstrumentType) -> Self {
+        let (tuning, fret_count): (&[&str], usize) = match instrument_type {
+            InstrumentType::Guitar => (&["E2", "A2", "D3", "G3", "B3", "E4"], 24),
+            InstrumentType::Bass => (&["E1", "A1", "D2", "G2"], 24),
+            InstrumentType::Mandolin => (&["G3", "D4", "A4", "E5"], 20),
+            InstrumentType::Banjo => (&["G4", "D3", "G3", "B3", "D4"], 22),
+            InstrumentType::Ukelelle => (&["G4", "C4", "E4", "A4"], 18),
+        };
+        let tuning: Vec<NotePitch> = tuning
+            .iter()
+            .filter_map(|pitch| NotePitch::parse(pitch))
+            .collect();
+        Instrument::from_tuning(instrument_type, &TuningType::Standard, &tuning, fret_count)
+    }
+
+    // Frets are counted in i8 semitones above the open strings, so necks are kept well short of
+    // where that overflows.
+    fn check_size(tuning: &[NotePitch], fret_count: usize) -> io::Result<()> {
+        let message = match (tuning.is_empty(), fret_count) {
+            (true, _) => "an instrument needs at least one string".to_string(),
+            (false, 1..=MAX_FRETS) => return Ok(()),
+            (false, _) => format!("an instrument needs between 1 and {} frets", MAX_FRETS),
+        };
+        Err(io::Error::new(io::ErrorKind::InvalidData, message))
+    }
+
+    // Type and open strings, e.g. "Mandolin (G3 D4 A4 E5)".
+    fn describe(&self) -> String {
+        let strings: Vec<String> = self
+            .tuning
+            .iter()
+            .map(|pitch| Theme::printable(&pitch.get_name()))
+            .collect();
+        format!("{} ({})", self.instrument_type, strings.join(" "))
+    }
+
+    // Each string's tension in pounds with its gauge, and whether it falls outside the
+    // instrument type's comfortable range.
+    fn string_tensions(&self) -> Vec<(f64, TensionStatus)> {
+        let (slack, tight) = self.instrument_type.tension_range();
+        self.tuning
+            .iter()
+            .zip(&self.string_gauges)
+            .enumerate()
+            .map(|(i, (pitch, gauge))| {
+                let length = self.scale_length.string_length(i, self.string_count);
+                let tension = gauge.tension(pitch, length);
+                let status = match tension {
+                    t if t < slack => TensionStatus::TooSlack,
+                    t if t > tight => TensionStatus::TooTight,
+                    _ => TensionStatus::Ok,
+                };
+                (tension, status)
+            })
+            .collect()
+    }
+
+    // One line of tensions from the lowest string, e.g. "Tension (lb): 16.2, 15.1, 8.9 too slack".
+    fn tension_summary(&self) -> String {
+        let tensions: Vec<String> = self
+            .string_tensions()
+            .iter()
+            .map(|&(tension, status)| match status {
+                TensionStatus::Ok => format!("{:.1}", tension),
+                _ => format!("{:.1} {}", tension, status),
+            })
+            .collect();
+        format!("Tension (lb): {}", tensions.join(", "))
+    }
+
     fn calculate_notes(self: &mut Self) {
         let mut notes: Vec<Vec<NoteDisplay>> = Vec::new();
         for i in 0..self.string_count {
             let mut musical_string: Vec<NoteDisplay> = Vec::new();
             for j in 0..(self.fret_count + 1) {
-                musical_string.push(NoteDisplay::new(
-                    &NotePitch::find_note(&self.tuning[i], j.try_into().unwrap()),
-                    true,
-                ));
+                musical_string.push(NoteDisplay::new(&NotePitch::find_note(
+                    &self.tuning[i],
+                    j.try_into().unwrap(),
+                )));
             }
             notes.push(musical_string.clone());
         }
@@ -165,2064 +238,8800 @@ impl Instrument {
         }
     }
 
+    // Works the open strings out again from a root note and tuning type.
+    fn retune(&mut self, tuning_type: &TuningType, root_note: &NotePitch) {
+        self.tuning_type = tuning_type.clone();
+        self.root_note = root_note.clone();
+        self.tuning = Vec::new();
+        self.calculate_tuning();
+        self.calculate_notes();
+    }
+
+    // Only six string guitars and four string basses have their tunings worked out by type.
+    fn has_tuning_types(&self) -> bool {
+        let shaped = matches!(
+            (&self.instrument_type, self.string_count),
+            (InstrumentType::Guitar, 6) | (InstrumentType::Bass, 4)
+        );
+        shaped && self.tuning_type != TuningType::Custom
+    }
+
+    // Moves every open string by the same number of semitones, keeping the intervals between them.
+    fn transpose(&mut self, semitones: i16) {
+        self.tuning = self
+            .tuning
+            .iter()
+            .map(|pitch| NotePitch::from_semitones(pitch.to_semitones() + semitones))
+            .collect();
+        self.root_note = NotePitch::from_semitones(self.root_note.to_semitones() + semitones);
+        self.calculate_notes();
+    }
+
+    // The show_ functions replace the base layers with a single one; add_layer composes user
+    // layers on top, and those outlive any later show_ call.
     fn show_all(self: &mut Self) {
-        for i in 0..self.string_count {
-            for j in 0..self.fret_count {
-                self.fretboard[i][j].is_displayed = true;
+        let positions = self.positions_where(|_| true);
+        self.layers = vec![HighlightLayer::new(
+            "all",
+            &HighlightStyle::Normal,
+            positions,
+        )];
+    }
+
+    fn show_notes(self: &mut Self, notes: &[NoteName]) {
+        self.layers = vec![self.notes_layer("notes", &HighlightStyle::Normal, notes)];
+    }
+
+    // Matches exact pitches rather than note names, so E2 and E4 can be told apart.
+    fn show_pitches(&mut self, low: &NotePitch, high: &NotePitch) {
+        self.layers = vec![self.pitches_layer("pitches", &HighlightStyle::Normal, low, high)];
+    }
+
+    fn show_positions(&mut self, positions: &[(usize, usize)]) {
+        let layer = HighlightLayer::new("positions", &HighlightStyle::Normal, positions.to_vec());
+        self.layers = vec![layer];
+    }
+
+    // Every position on the neck, open strings through the last fret, whose note passes test.
+    fn positions_where(&self, test: impl Fn(&NotePitch) -> bool) -> Vec<(usize, usize)> {
+        let mut positions: Vec<(usize, usize)> = Vec::new();
+        for (i, musical_string) in self.fretboard.iter().enumerate() {
+            for (j, note_display) in musical_string.iter().enumerate() {
+                if test(&note_display.note_pitch) {
+                    positions.push((i, j));
+                }
             }
         }
+        positions
     }
 
-    fn show_notes(self: &mut Self, notes: &Vec<NoteName>) {
-        for i in 0..self.string_count {
-            for j in 0..self.fret_count {
-                self.fretboard[i][j].is_displayed = false;
+    // Every position whose note is one of notes, in any octave.
+    fn note_positions(&self, notes: &[NoteName]) -> Vec<(usize, usize)> {
+        let numbers: Vec<i8> = notes.iter().map(|note| note.to_number()).collect();
+        self.positions_where(|pitch| numbers.contains(&pitch.note_name.to_number()))
+    }
+
+    fn notes_layer(
+        &self,
+        name: &str,
+        style: &HighlightStyle,
+        notes: &[NoteName],
+    ) -> HighlightLayer {
+        HighlightLayer::new(name, style, self.note_positions(notes))
+    }
+
+    fn pitches_layer(
+        &self,
+        name: &str,
+        style: &HighlightStyle,
+        low: &NotePitch,
+        high: &NotePitch,
+    ) -> HighlightLayer {
+        let range = low.to_semitones()..=high.to_semitones();
+        let positions = self.positions_where(|pitch| range.contains(&pitch.to_semitones()));
+        HighlightLayer::new(name, style, positions)
+    }
+
+    // Puts the layer on top, or swaps it in place of an existing layer with the same name.
+    fn add_layer(&mut self, layer: HighlightLayer) {
+        match self
+            .user_layers
+            .iter()
+            .position(|existing| existing.name == layer.name)
+        {
+            Some(index) => self.user_layers[index] = layer,
+            None => self.user_layers.push(layer),
+        }
+    }
+
+    fn remove_layer(&mut self, name: &str) -> bool {
+        let count = self.layers.len() + self.user_layers.len();
+        self.layers
+            .retain(|layer| !layer.name.eq_ignore_ascii_case(name));
+        self.user_layers
+            .retain(|layer| !layer.name.eq_ignore_ascii_case(name));
+        self.layers.len() + self.user_layers.len() != count
+    }
+
+    fn clear_user_layers(&mut self) {
+        self.user_layers.clear();
+    }
+
+    // Base layers first, then user layers, bottom to top.
+    fn all_layers(&self) -> impl DoubleEndedIterator<Item = &HighlightLayer> {
+        self.layers.iter().chain(self.user_layers.iter())
+    }
+
+    // The topmost layer covering a position decides how it's drawn.
+    fn top_layer(&self, string: usize, fret: usize) -> Option<&HighlightLayer> {
+        self.all_layers()
+            .rev()
+            .find(|layer| layer.positions.contains(&(string, fret)))
+    }
+
+    // Every string and fret sounding exactly this pitch, lowest string first.
+    fn find_pitch(&self, pitch: &NotePitch) -> Vec<(usize, usize)> {
+        let mut positions: Vec<(usize, usize)> = Vec::new();
+        for (i, musical_string) in self.fretboard.iter().enumerate() {
+            for (j, note_display) in musical_string.iter().enumerate() {
+                if note_display.note_pitch.to_semitones() == pitch.to_semitones() {
+                    positions.push((i, j));
+                }
             }
         }
-        for note in notes {
-            for i in 0..self.string_count {
-                for j in 0..self.fret_count {
-                    if note.to_number() == self.fretboard[i][j].note_pitch.note_name.to_number() {
-                        self.fretboard[i][j].is_displayed = true;
+        positions
+    }
+
+    // Every natural harmonic node from the nut to the last fret, by string then partial. Nodes
+    // sharing a factor with the partial belong to a lower one and are skipped.
+    fn harmonics(&self) -> Vec<Harmonic> {
+        let mut harmonics: Vec<Harmonic> = Vec::new();
+        for (i, open_note) in self.tuning.iter().enumerate() {
+            for partial in 2..=HIGHEST_PARTIAL {
+                let interval = 12.0 * (partial as f64).log2();
+                let semitones = open_note.to_semitones() + interval.round() as i16;
+                for node in 1..partial {
+                    if (2..=node).any(|d| node % d == 0 && partial % d == 0) {
+                        continue;
                     }
+                    let fret = 12.0 * (partial as f64 / (partial - node) as f64).log2();
+                    if fret > self.fret_count as f64 {
+                        continue;
+                    }
+                    harmonics.push(Harmonic {
+                        string: i,
+                        partial,
+                        fret,
+                        pitch: NotePitch::from_semitones(semitones),
+                        cents: (interval - interval.round()) * 100.0,
+                    });
                 }
             }
         }
+        harmonics
     }
-}
 
-#[derive(PartialEq, Clone, Debug)]
-struct NoteDisplay {
-    note_pitch: NotePitch,
-    is_displayed: bool,
-}
+    fn find_harmonic(&self, pitch: &NotePitch) -> Vec<Harmonic> {
+        self.harmonics()
+            .into_iter()
+            .filter(|harmonic| harmonic.pitch.to_semitones() == pitch.to_semitones())
+            .collect()
+    }
 
-impl NoteDisplay {
-    fn new(note_pitch: &NotePitch, is_displayed: bool) -> Self {
-        NoteDisplay {
-            note_pitch: note_pitch.clone(),
-            is_displayed,
+    fn fret_table(&self, temperament: &Temperament) -> FretTable {
+        FretTable {
+            scale_length: self.scale_length.clone(),
+            temperament: temperament.clone(),
+            string_count: self.string_count,
+            fret_count: self.fret_count,
         }
     }
-}
 
-impl Display for NoteDisplay {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        match self.is_displayed {
-            true => {
-                let rgb = self.note_pitch.note_name.to_rgb();
-                write!(f, "{}", self.note_pitch.get_name().color(rgb))?;
-                Ok(())
+    // The lowest and highest fret with a displayed note, if any.
+    fn highlighted_frets(&self) -> Option<(usize, usize)> {
+        let frets: Vec<usize> = self
+            .all_layers()
+            .flat_map(|layer| layer.positions.iter().map(|&(_, j)| j))
+            .collect();
+        Some((*frets.iter().min()?, *frets.iter().max()?))
+    }
+
+    // Standard tuning in any key: fourths between strings except a major third onto the second.
+    fn is_standard_guitar_tuning(&self) -> bool {
+        let steps: Vec<i16> = self
+            .tuning
+            .windows(2)
+            .map(|pair| pair[1].to_semitones() - pair[0].to_semitones())
+            .collect();
+        steps == vec![5, 5, 5, 4, 5]
+    }
+
+    // Places each CAGED shape at the lowest fret its root allows, sorted up the neck. Scale
+    // positions reach one fret further either side of the chord shape.
+    fn caged_regions(&self, root: &NoteName, for_scale: bool) -> Vec<CagedRegion> {
+        let widen: i8 = match for_scale {
+            true => 1,
+            false => 0,
+        };
+        let mut regions: Vec<CagedRegion> = Vec::new();
+        for shape in CagedShape::all() {
+            let (root_string, low, high) = shape.anchor();
+            let open = self.tuning[root_string].note_name.to_number();
+            let mut root_fret = NoteName::minus(root, open);
+            if root_fret + low < 0 {
+                root_fret += 12;
             }
-            false => {
-                match &self.note_pitch.note_name.accidental {
-                    Some(_accidental) => write!(f, "   ")?,
-                    None => write!(f, "  ")?,
-                }
+            let first_fret = (root_fret + low - widen).max(0) as usize;
+            let last_fret = (root_fret + high + widen) as usize;
+            if last_fret <= self.fret_count {
+                regions.push(CagedRegion {
+                    shape,
+                    first_fret,
+                    last_fret,
+                });
+            }
+        }
+        regions.sort_by_key(|region| region.first_fret);
+        regions
+    }
 
-                Ok(())
+    // Patterns start on each degree of the scale in turn, numbered from the root. Notes per
+    // string systems climb the scale string by string from the lowest string; one octave shapes
+    // play root to root within a five fret window from each root string.
+    fn scale_patterns(&self, scale: &Scale, system: &PatternSystem) -> Vec<ScalePattern> {
+        // The walks below climb the scale, so a definition listed out of order is sorted
+        // upwards from its root first.
+        let mut numbers = scale.pitch_classes();
+        let Some(&root) = numbers.first() else {
+            return Vec::new();
+        };
+        numbers.sort_by_key(|&number| (number - root).rem_euclid(12));
+        if system
+            .note_count()
+            .is_some_and(|count| count != numbers.len())
+        {
+            return Vec::new();
+        }
+        let mut patterns: Vec<ScalePattern> = Vec::new();
+        match system {
+            PatternSystem::ThreeNotesPerString | PatternSystem::TwoNotesPerString => {
+                let per_string = match system {
+                    PatternSystem::ThreeNotesPerString => 3,
+                    _ => 2,
+                };
+                for degree in 0..numbers.len() {
+                    let open = self.tuning[0].note_name.to_number();
+                    let fret = (numbers[degree] - open).rem_euclid(12) as i16;
+                    let start = self.tuning[0].to_semitones() + fret;
+                    let Some(frets) = self
+                        .walk_strings(&numbers, degree, start, per_string)
+                        .or_else(|| self.walk_strings(&numbers, degree, start + 12, per_string))
+                    else {
+                        continue;
+                    };
+                    let mut notes: Vec<(usize, usize, u8)> = Vec::new();
+                    for (i, string_frets) in frets.iter().enumerate() {
+                        let fingers = ScalePattern::string_fingers(string_frets);
+                        for (&fret, finger) in string_frets.iter().zip(fingers) {
+                            notes.push((i, fret, finger));
+                        }
+                    }
+                    patterns.push(ScalePattern {
+                        system: system.clone(),
+                        number: degree + 1,
+                        tuning: self.tuning.clone(),
+                        notes,
+                    });
+                }
+            }
+            PatternSystem::OneOctave => {
+                let mut shapes: Vec<Vec<(usize, usize, u8)>> = Vec::new();
+                for root_string in 0..self.string_count.saturating_sub(1) {
+                    let open = self.tuning[root_string].note_name.to_number();
+                    let root_fret = (numbers[0] - open).rem_euclid(12) as usize;
+                    if let Some(notes) = self.octave_shape(&numbers, root_string, root_fret) {
+                        shapes.push(notes);
+                    }
+                }
+                shapes.sort_by_key(|notes| notes.iter().map(|&(_, fret, _)| fret).min());
+                for (number, notes) in shapes.into_iter().enumerate() {
+                    patterns.push(ScalePattern {
+                        system: system.clone(),
+                        number: number + 1,
+                        tuning: self.tuning.clone(),
+                        notes,
+                    });
+                }
             }
         }
+        patterns
     }
-}
 
-#[derive(PartialEq, Clone, Debug)]
-struct NotePitch {
-    note_name: NoteName,
-    octave: i8,
-}
+    // Climbs the scale from the pitch start, which is the given degree, placing per_string notes
+    // on each string in turn. None if a note falls below the nut or beyond the last fret.
+    fn walk_strings(
+        &self,
+        numbers: &[i8],
+        degree: usize,
+        start: i16,
+        per_string: usize,
+    ) -> Option<Vec<Vec<usize>>> {
+        let mut pitch = start;
+        let mut index = degree;
+        let mut frets: Vec<Vec<usize>> = Vec::new();
+        for open_note in &self.tuning {
+            let mut string_frets: Vec<usize> = Vec::new();
+            for _ in 0..per_string {
+                let fret = pitch - open_note.to_semitones();
+                if fret < 0 || fret as usize > self.fret_count {
+                    return None;
+                }
+                string_frets.push(fret as usize);
+                let next = numbers[(index + 1) % numbers.len()];
+                pitch += (next - numbers[index % numbers.len()]).rem_euclid(12) as i16;
+                index += 1;
+            }
+            frets.push(string_frets);
+        }
+        Some(frets)
+    }
 
-impl Display for NotePitch {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let rgb = self.note_name.to_rgb();
-        match &self.note_name.accidental {
-            Some(_accidental) => write!(f, "{}", self.get_name().color(rgb)),
-            None => write!(f, "\u{2002}{}", self.get_name().color(rgb)),
+    // Root to octave from root_fret on root_string, each note on the lowest string at or above
+    // the previous one that keeps it within a fret behind to three frets past the root.
+    fn octave_shape(
+        &self,
+        numbers: &[i8],
+        root_string: usize,
+        root_fret: usize,
+    ) -> Option<Vec<(usize, usize, u8)>> {
+        let low = root_fret.saturating_sub(1) as i16;
+        let high = root_fret as i16 + 3;
+        if high as usize > self.fret_count {
+            return None;
+        }
+        let first_finger_fret = low.max(1);
+        let mut pitch = self.tuning[root_string].to_semitones() + root_fret as i16;
+        let mut string = root_string;
+        let mut notes: Vec<(usize, usize, u8)> = Vec::new();
+        for index in 0..=numbers.len() {
+            loop {
+                let fret = pitch - self.tuning.get(string)?.to_semitones();
+                if (low..=high).contains(&fret) {
+                    let finger = match fret {
+                        0 => 0,
+                        _ => (fret - first_finger_fret + 1).clamp(1, 4) as u8,
+                    };
+                    notes.push((string, fret as usize, finger));
+                    break;
+                }
+                string += 1;
+            }
+            let next = numbers[(index + 1) % numbers.len()];
+            pitch += (next - numbers[index % numbers.len()]).rem_euclid(12) as i16;
         }
+        Some(notes)
     }
-}
 
-impl NotePitch {
-    fn new(natural_note: &NaturalNote, accidental: &Option<Accidental>, octave: i8) -> Self {
-        NotePitch {
-            note_name: NoteName::new(natural_note, accidental),
-            octave,
+    // The chord tones under the voicing's chord box, low to high, one per pitch.
+    fn arpeggio(&self, chord: &Chord, voicing: &Voicing) -> Vec<(usize, usize)> {
+        let first_fret = voicing.first_fret();
+        let low = match first_fret {
+            1 => 0,
+            _ => first_fret,
+        };
+        let high = (first_fret + voicing.fret_span() - 1).min(self.fret_count);
+        let numbers: Vec<i8> = chord.notes.iter().map(|note| note.to_number()).collect();
+        let mut notes: Vec<(usize, usize)> = Vec::new();
+        let mut previous: Option<i16> = None;
+        for (i, open_note) in self.tuning.iter().enumerate() {
+            for fret in low..=high {
+                let pitch = open_note.to_semitones() + fret as i16;
+                let number = self.fretboard[i][fret].note_pitch.note_name.to_number();
+                if numbers.contains(&number) && previous.is_none_or(|p| pitch > p) {
+                    notes.push((i, fret));
+                    previous = Some(pitch);
+                }
+            }
         }
+        notes
     }
 
-    fn get_name(self: &Self) -> String {
-        format!("{}{}", self.note_name.get_name(), self.octave)
+    // The pitches a voicing sounds in string order, from the lowest string as a downstroke
+    // meets them.
+    fn voicing_pitches(&self, voicing: &Voicing) -> Vec<NotePitch> {
+        voicing
+            .frets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, fret)| fret.map(|fret| self.fretboard[i][fret].note_pitch.clone()))
+            .collect()
     }
 
-    fn from_note_name(note_name: &NoteName, octave: i8) -> Self {
-        NotePitch {
-            note_name: note_name.clone(),
-            octave,
+    // A chord as voiced on the neck, or stacked up from the third octave when there's no voicing
+    // within reach.
+    fn chord_pitches(&self, chord: &Chord) -> Vec<NotePitch> {
+        match self.find_voicing(chord) {
+            Some(voicing) => self.voicing_pitches(&voicing),
+            None => NotePitch::stack(&chord.notes, 3),
         }
     }
 
-    fn from_number(note_number: i8, octave: i8) -> Self {
-        NotePitch {
-            note_name: NoteName::from_number(note_number),
-            octave,
-        }
+    fn is_marked_fret(fret: usize) -> bool {
+        MARKED_FRETS.contains(&fret)
     }
 
-    fn to_number(self: &Self) -> (i8, i8) {
-        (self.note_name.to_number(), self.octave)
+    // Tries every four-fret position (open strings only in the first) and keeps the voicing that
+    // covers the most chord tones, then prefers the root in the bass, no muted strings between
+    // sounding ones, the lowest position and finally the most strings sounding.
+    fn find_voicing(&self, chord: &Chord) -> Option<Voicing> {
+        let chord_numbers: Vec<i8> = chord.notes.iter().map(|note| note.to_number()).collect();
+        let root = chord_numbers[0];
+        let last_base_fret = self.fret_count.saturating_sub(VOICING_SPAN - 1).max(1);
+        let mut best: Option<(VoicingScore, Vec<Option<usize>>)> = None;
+        for base_fret in 1..=last_base_fret {
+            let mut candidates: Vec<Vec<Option<usize>>> = Vec::new();
+            for i in 0..self.string_count {
+                let mut frets: Vec<Option<usize>> = vec![None];
+                let window = base_fret..(base_fret + VOICING_SPAN).min(self.fret_count + 1);
+                for fret in std::iter::once(0).filter(|_| base_fret == 1).chain(window) {
+                    let number = self.fretboard[i][fret].note_pitch.note_name.to_number();
+                    if chord_numbers.contains(&number) {
+                        frets.push(Some(fret));
+                    }
+                }
+                candidates.push(frets);
+            }
+            let mut choice: Vec<usize> = vec![0; self.string_count];
+            loop {
+                let frets: Vec<Option<usize>> = choice
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &c)| candidates[i][c])
+                    .collect();
+                if let Some(score) = self.score_voicing(&frets, &chord_numbers, root, base_fret) {
+                    match &best {
+                        Some((best_score, _)) if *best_score >= score => (),
+                        _ => best = Some((score, frets)),
+                    }
+                }
+                let mut i = 0;
+                while i < self.string_count {
+                    choice[i] += 1;
+                    if choice[i] < candidates[i].len() {
+                        break;
+                    }
+                    choice[i] = 0;
+                    i += 1;
+                }
+                if i == self.string_count {
+                    break;
+                }
+            }
+        }
+        best.map(|(_, frets)| Voicing::new(&frets))
     }
 
-    fn find_note(open_note: &NotePitch, distance: i8) -> Self {
-        let (x, y) = match distance {
-            i if i > 0 => NotePitch::add(&open_note, distance),
-            i if i < 0 => NotePitch::minus(&open_note, distance),
-            0 => return open_note.clone(),
-            _ => panic!("unexpected distance"),
-        };
-        NotePitch::from_number(x, y)
+    // Returns None for voicings that can't be fingered: nothing sounding, or more than four
+    // fingers once every note on the lowest fretted fret is treated as one barre.
+    fn score_voicing(
+        &self,
+        frets: &[Option<usize>],
+        chord_numbers: &[i8],
+        root: i8,
+        base_fret: usize,
+    ) -> Option<VoicingScore> {
+        let sounding: Vec<(usize, usize)> = frets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, fret)| fret.map(|fret| (i, fret)))
+            .collect();
+        let (bass_string, bass_fret) = *sounding.first()?;
+        let fretted: Vec<usize> = sounding
+            .iter()
+            .map(|&(_, fret)| fret)
+            .filter(|&fret| fret > 0)
+            .collect();
+        if let Some(&lowest) = fretted.iter().min() {
+            let fingers = 1 + fretted.iter().filter(|&&fret| fret != lowest).count();
+            if fingers > 4 {
+                return None;
+            }
+        }
+        let mut covered: Vec<i8> = sounding
+            .iter()
+            .map(|&(i, fret)| self.fretboard[i][fret].note_pitch.note_name.to_number())
+            .collect();
+        covered.sort();
+        covered.dedup();
+        let covered = chord_numbers
+            .iter()
+            .filter(|number| covered.contains(number))
+            .count();
+        let root_in_bass = self.fretboard[bass_string][bass_fret]
+            .note_pitch
+            .note_name
+            .to_number()
+            == root;
+        let (top_string, _) = *sounding.last()?;
+        let no_gaps = sounding.len() == top_string - bass_string + 1;
+        Some((
+            covered,
+            root_in_bass,
+            no_gaps,
+            Reverse(base_fret),
+            sounding.len(),
+        ))
     }
+}
 
-    fn up_step(start_note: &NotePitch, step: &Step) -> Self {
-        let to_add = step.to_number();
-        let (number, octave) = NotePitch::add(start_note, to_add);
-        NotePitch::from_number(number, octave)
+const MARKED_FRETS: [usize; 11] = [1, 3, 5, 7, 9, 12, 15, 17, 19, 21, 24];
+
+// Frets a hand covers in one position when searching for chord voicings.
+const VOICING_SPAN: usize = 4;
+
+// Chord tones covered, root in the bass, no inner muted strings, position, strings sounding.
+type VoicingScore = (usize, bool, bool, Reverse<usize>, usize);
+
+#[derive(PartialEq, Clone, Debug)]
+enum CagedShape {
+    C,
+    A,
+    G,
+    E,
+    D,
+}
+
+impl Display for CagedShape {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            CagedShape::C => write!(f, "C")?,
+            CagedShape::A => write!(f, "A")?,
+            CagedShape::G => write!(f, "G")?,
+            CagedShape::E => write!(f, "E")?,
+            CagedShape::D => write!(f, "D")?,
+        }
+        Ok(())
     }
+}
 
-    fn down_step(start_note: &NotePitch, step: &Step) -> Self {
-        let to_subtract = step.to_number();
-        let (number, octave) = NotePitch::minus(start_note, to_subtract);
-        NotePitch::from_number(number, octave)
+impl CagedShape {
+    fn all() -> Vec<CagedShape> {
+        vec![
+            CagedShape::C,
+            CagedShape::A,
+            CagedShape::G,
+            CagedShape::E,
+            CagedShape::D,
+        ]
     }
 
-    fn add(start_note: &NotePitch, to_add: i8) -> (i8, i8) {
-        let (note_number, octave) = start_note.to_number();
-        let mut octave = octave;
-        let mut number = note_number + to_add;
-        while number >= 12 {
-            number = number - 12;
-            octave = octave + 1;
+    // None asks for every shape.
+    fn from_string(input: String) -> Option<Self> {
+        match input.to_uppercase().as_str() {
+            "C" => Some(CagedShape::C),
+            "A" => Some(CagedShape::A),
+            "G" => Some(CagedShape::G),
+            "E" => Some(CagedShape::E),
+            "D" => Some(CagedShape::D),
+            "ALL" => None,
+            _ => {
+                println!("Enter a CAGED shape (C, A, G, E, D, All):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                CagedShape::from_string(reinput.trim().to_string())
+            }
         }
-        (number, octave)
     }
 
-    fn minus(start_note: &NotePitch, to_subtract: i8) -> (i8, i8) {
-        let (note_number, octave) = start_note.to_number();
-        let mut octave = octave;
-        let mut number: i8 = note_number - to_subtract;
-        while number < 0 {
-            number = number + 12;
-            octave = octave - 1;
+    // The string the root sits on (lowest = 0) and the frets the shape spans either side of
+    // it, taken from the open chord the shape is named after. Minor shapes span the same frets.
+    fn anchor(&self) -> (usize, i8, i8) {
+        match self {
+            CagedShape::C => (1, -3, 0),
+            CagedShape::A => (1, 0, 2),
+            CagedShape::G => (0, -3, 0),
+            CagedShape::E => (0, 0, 2),
+            CagedShape::D => (2, 0, 3),
         }
-        (number, octave)
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
-struct NoteName {
-    natural_note: NaturalNote,
-    accidental: Option<Accidental>,
+#[derive(PartialEq, Clone, Debug)]
+struct CagedRegion {
+    shape: CagedShape,
+    first_fret: usize,
+    last_fret: usize,
 }
 
-impl Display for NoteName {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let rgb = self.to_rgb();
-        write!(f, "{}", self.get_name().color(rgb))?;
-        Ok(())
-    }
+#[derive(PartialEq, Clone, Debug)]
+struct NoteDisplay {
+    note_pitch: NotePitch,
 }
 
-impl Ord for NoteName {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.to_number().cmp(&other.to_number())
+impl NoteDisplay {
+    fn new(note_pitch: &NotePitch) -> Self {
+        NoteDisplay {
+            note_pitch: note_pitch.clone(),
+        }
     }
 }
 
-impl PartialOrd for NoteName {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl Display for NoteDisplay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        let rgb = theme.note_rgb(&self.note_pitch.note_name);
+        write!(f, "{}", theme.paint(self.note_pitch.get_name(), rgb))
     }
 }
 
-impl NoteName {
-    fn new(natural_note: &NaturalNote, accidental: &Option<Accidental>) -> Self {
-        NoteName {
-            natural_note: natural_note.clone(),
-            accidental: accidental.clone(),
+#[derive(PartialEq, Clone, Debug)]
+enum HighlightStyle {
+    Dim,
+    Normal,
+    Bright,
+    Bold,
+    Blink,
+}
+
+impl Display for HighlightStyle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            HighlightStyle::Dim => write!(f, "Dim")?,
+            HighlightStyle::Normal => write!(f, "Normal")?,
+            HighlightStyle::Bright => write!(f, "Bright")?,
+            HighlightStyle::Bold => write!(f, "Bold")?,
+            HighlightStyle::Blink => write!(f, "Blink")?,
         }
+        Ok(())
     }
+}
 
-    fn get_name(self: &Self) -> String {
-        let natural_note = match self.natural_note {
-            NaturalNote::A => "A".to_string(),
-            NaturalNote::B => "B".to_string(),
-            NaturalNote::C => "C".to_string(),
-            NaturalNote::D => "D".to_string(),
-            NaturalNote::E => "E".to_string(),
-            NaturalNote::F => "F".to_string(),
-            NaturalNote::G => "G".to_string(),
-        };
-        let accidental = match self.accidental {
-            Some(Accidental::Sharp) => "♯".to_string(),
-            Some(Accidental::Flat) => "♭".to_string(),
-            None => "".to_string(),
-        };
-        let name = natural_note + &accidental;
-        name
-    }
-
-    fn from_number(note_number: i8) -> NoteName {
-        match note_number {
-            0 => NoteName::new(&NaturalNote::C, &None),
-            1 => NoteName::new(&NaturalNote::C, &Some(Accidental::Sharp)),
-            2 => NoteName::new(&NaturalNote::D, &None),
-            3 => NoteName::new(&NaturalNote::D, &Some(Accidental::Sharp)),
-            4 => NoteName::new(&NaturalNote::E, &None),
-            5 => NoteName::new(&NaturalNote::F, &None),
-            6 => NoteName::new(&NaturalNote::F, &Some(Accidental::Sharp)),
-            7 => NoteName::new(&NaturalNote::G, &None),
-            8 => NoteName::new(&NaturalNote::G, &Some(Accidental::Sharp)),
-            9 => NoteName::new(&NaturalNote::A, &None),
-            10 => NoteName::new(&NaturalNote::A, &Some(Accidental::Sharp)),
-            11 => NoteName::new(&NaturalNote::B, &None),
-            _ => panic!("unexpected note number"),
-        }
-    }
-
-    fn to_number(self: &Self) -> i8 {
-        let mut number: i8 = match self.natural_note {
-            NaturalNote::C => {
-                0 + match self.accidental {
-                    Some(Accidental::Flat) => panic!("unexpected accidental"),
-                    Some(Accidental::Sharp) => 1,
-                    None => 0,
-                }
-            }
-            NaturalNote::D => {
-                2 + match self.accidental {
-                    Some(Accidental::Flat) => -1,
-                    Some(Accidental::Sharp) => 1,
-                    None => 0,
-                }
-            }
-            NaturalNote::E => {
-                4 + match self.accidental {
-                    Some(Accidental::Flat) => -1,
-                    Some(Accidental::Sharp) => panic!("unexpected accidental"),
-                    None => 0,
-                }
-            }
-            NaturalNote::F => {
-                5 + match self.accidental {
-                    Some(Accidental::Flat) => panic!("unexpected accidental"),
-                    Some(Accidental::Sharp) => 1,
-                    None => 0,
-                }
-            }
-            NaturalNote::G => {
-                7 + match self.accidental {
-                    Some(Accidental::Flat) => -1,
-                    Some(Accidental::Sharp) => 1,
-                    None => 0,
-                }
-            }
-            NaturalNote::A => {
-                9 + match self.accidental {
-                    Some(Accidental::Flat) => -1,
-                    Some(Accidental::Sharp) => 1,
-                    None => 0,
-                }
-            }
-            NaturalNote::B => {
-                11 + match self.accidental {
-                    Some(Accidental::Flat) => -1,
-                    Some(Accidental::Sharp) => panic!("unexpected accidental"),
-                    None => 0,
-                }
+impl HighlightStyle {
+    // An empty input means the caller's default style.
+    fn from_string(input: String) -> Option<Self> {
+        match input.to_uppercase().as_str() {
+            "" => None,
+            "DIM" => Some(HighlightStyle::Dim),
+            "NORMAL" => Some(HighlightStyle::Normal),
+            "BRIGHT" => Some(HighlightStyle::Bright),
+            "BOLD" => Some(HighlightStyle::Bold),
+            "BLINK" => Some(HighlightStyle::Blink),
+            _ => {
+                println!("Enter a style (Dim, Normal, Bright, Bold, Blink):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                HighlightStyle::from_string(reinput.trim().to_string())
             }
-        };
-        if number > 11 {
-            number = number - 11;
         }
-        number
     }
 
-    fn up_step(start_note: &NoteName, step: &Step) -> NoteName {
-        let to_add = step.to_number();
-        let number = NoteName::add(start_note, to_add);
-        NoteName::from_number(number)
+    // The SGR attribute switched on around a note and the code that switches it off again.
+    fn sgr(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            HighlightStyle::Dim => Some(("2", "22")),
+            HighlightStyle::Bold => Some(("1", "22")),
+            HighlightStyle::Blink => Some(("5", "25")),
+            HighlightStyle::Normal | HighlightStyle::Bright => None,
+        }
     }
 
-    fn down_step(start_note: &NoteName, step: &Step) -> NoteName {
-        let to_subtract = step.to_number();
-        let number = NoteName::minus(start_note, to_subtract);
-        NoteName::from_number(number)
+    // Without ANSI every style would look the same, so a character after the note tells them apart.
+    fn marker(&self) -> Option<char> {
+        match self {
+            HighlightStyle::Dim => Some('.'),
+            HighlightStyle::Normal => None,
+            HighlightStyle::Bright => Some('+'),
+            HighlightStyle::Bold => Some('*'),
+            HighlightStyle::Blink => Some('!'),
+        }
     }
 
-    fn add(start_note: &NoteName, to_add: i8) -> i8 {
-        let note_number = start_note.to_number();
-        let mut number = note_number + to_add;
-        while number >= 12 {
-            number = number - 12;
+    // Bright notes are lifted halfway to white.
+    fn adjust(&self, rgb: Rgb) -> Rgb {
+        let Rgb(r, g, b) = rgb;
+        match self {
+            HighlightStyle::Bright => Rgb(
+                ((r as u16 + 255) / 2) as u8,
+                ((g as u16 + 255) / 2) as u8,
+                ((b as u16 + 255) / 2) as u8,
+            ),
+            _ => rgb,
         }
-        number
     }
 
-    fn minus(start_note: &NoteName, to_subtract: i8) -> i8 {
-        let note_number = start_note.to_number();
-        let mut number: i8 = note_number - to_subtract;
-        while number < 0 {
-            number = number + 12;
+    // The SVG counterpart of sgr: faded, outlined, or a dashed outline standing in for blinking.
+    fn svg_attributes(&self) -> &'static str {
+        match self {
+            HighlightStyle::Dim => " fill-opacity=\"0.4\"",
+            HighlightStyle::Bold => " stroke=\"black\" stroke-width=\"2\"",
+            HighlightStyle::Blink => " stroke=\"black\" stroke-dasharray=\"3,2\"",
+            HighlightStyle::Normal | HighlightStyle::Bright => "",
         }
-        number
     }
+}
 
-    fn to_rgb(self: &Self) -> Rgb {
-        match self.natural_note {
-            NaturalNote::C => match self.accidental {
-                Some(Accidental::Flat) => panic!("unexpected accidental"),
-                Some(Accidental::Sharp) => Rgb {
-                    0: 191,
-                    1: 64,
-                    2: 191,
-                },
-                None => Rgb { 0: 191, 1: 0, 2: 0 },
-            },
-            NaturalNote::D => match self.accidental {
-                Some(Accidental::Flat) => Rgb {
-                    0: 191,
-                    1: 64,
-                    2: 191,
-                },
-                Some(Accidental::Sharp) => Rgb {
-                    0: 198,
-                    1: 255,
-                    2: 0,
-                },
-                None => Rgb {
-                    0: 255,
-                    1: 191,
-                    2: 64,
-                },
-            },
-            NaturalNote::E => match self.accidental {
-                Some(Accidental::Flat) => Rgb {
-                    0: 198,
-                    1: 255,
-                    2: 0,
-                },
-                Some(Accidental::Sharp) => panic!("unexpected accidental"),
-                None => Rgb {
-                    0: 244,
-                    1: 67,
-                    2: 54,
-                },
-            },
-            NaturalNote::F => match self.accidental {
-                Some(Accidental::Flat) => panic!("unexpected accidental"),
-                Some(Accidental::Sharp) => Rgb {
-                    0: 255,
-                    1: 0,
-                    2: 191,
-                },
-                None => Rgb {
-                    0: 255,
-                    1: 0,
-                    2: 255,
-                },
-            },
-            NaturalNote::G => match self.accidental {
-                Some(Accidental::Flat) => Rgb {
-                    0: 255,
-                    1: 0,
-                    2: 191,
-                },
-                Some(Accidental::Sharp) => Rgb { 0: 0, 1: 191, 2: 0 },
-                None => Rgb { 0: 0, 1: 255, 2: 0 },
-            },
-            NaturalNote::A => match self.accidental {
-                Some(Accidental::Flat) => Rgb { 0: 0, 1: 191, 2: 0 },
-                Some(Accidental::Sharp) => Rgb {
-                    0: 64,
-                    1: 191,
-                    2: 191,
-                },
-                None => Rgb {
-                    0: 165,
-                    1: 255,
-                    2: 235,
-                },
-            },
-            NaturalNote::B => match self.accidental {
-                Some(Accidental::Flat) => Rgb {
-                    0: 64,
-                    1: 191,
-                    2: 191,
-                },
-                Some(Accidental::Sharp) => panic!("unexpected accidental"),
-                None => Rgb {
-                    0: 64,
-                    1: 31,
-                    2: 255,
-                },
-            },
+// What a highlight layer is built from, each with the style it gets unless one is chosen.
+#[derive(PartialEq, Clone, Debug)]
+enum LayerSource {
+    Scale,
+    Chord,
+    Voicing,
+    Root,
+    Target,
+}
+
+impl Display for LayerSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            LayerSource::Scale => write!(f, "scale")?,
+            LayerSource::Chord => write!(f, "chord")?,
+            LayerSource::Voicing => write!(f, "voicing")?,
+            LayerSource::Root => write!(f, "root")?,
+            LayerSource::Target => write!(f, "target")?,
         }
+        Ok(())
     }
+}
 
-    fn from_string(input: String) -> NoteName {
-        let input: String = input.to_uppercase();
-        match input.as_str() {
-            "A" => NoteName::new(&NaturalNote::A, &None),
-            "A#" => NoteName::new(&NaturalNote::A, &Some(Accidental::Sharp)),
-            "B" => NoteName::new(&NaturalNote::B, &None),
-            "C" => NoteName::new(&NaturalNote::C, &None),
-            "C#" => NoteName::new(&NaturalNote::C, &Some(Accidental::Sharp)),
-            "D" => NoteName::new(&NaturalNote::D, &None),
-            "D#" => NoteName::new(&NaturalNote::D, &Some(Accidental::Sharp)),
-            "E" => NoteName::new(&NaturalNote::E, &None),
-            "F" => NoteName::new(&NaturalNote::F, &None),
-            "F#" => NoteName::new(&NaturalNote::F, &Some(Accidental::Sharp)),
-            "G" => NoteName::new(&NaturalNote::G, &None),
-            "G#" => NoteName::new(&NaturalNote::G, &Some(Accidental::Sharp)),
+impl LayerSource {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "SCALE" => LayerSource::Scale,
+            "CHORD" => LayerSource::Chord,
+            "VOICING" => LayerSource::Voicing,
+            "ROOT" => LayerSource::Root,
+            "TARGET" => LayerSource::Target,
             _ => {
-                println!("Enter a new key (e.g., C, D#, F#):");
+                println!("Enter a layer (Scale, Chord, Voicing, Root, Target):");
                 let mut reinput = String::new();
                 io::stdin()
                     .read_line(&mut reinput)
                     .expect("Failed to read input");
-                let key = NoteName::from_string(reinput.trim().to_string());
-                return key;
+                LayerSource::from_string(reinput.trim().to_string())
             }
         }
     }
-}
-
-#[derive(PartialEq, Eq, Clone, Debug)]
-enum NaturalNote {
-    A,
-    B,
-    C,
-    D,
-    E,
-    F,
-    G,
-}
 
-impl Ord for NaturalNote {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.to_number().cmp(&other.to_number())
+    fn default_style(&self) -> HighlightStyle {
+        match self {
+            LayerSource::Scale => HighlightStyle::Dim,
+            LayerSource::Chord | LayerSource::Voicing => HighlightStyle::Bright,
+            LayerSource::Root => HighlightStyle::Bold,
+            LayerSource::Target => HighlightStyle::Blink,
+        }
     }
 }
 
-impl PartialOrd for NaturalNote {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+// A named set of positions drawn in one style. An instrument stacks several, e.g. a scale dimmed
+// under its chord tones with the root in bold.
+#[derive(PartialEq, Clone, Debug)]
+struct HighlightLayer {
+    name: String,
+    style: HighlightStyle,
+    positions: Vec<(usize, usize)>,
+}
+
+impl Display for HighlightLayer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{} ({}): {} positions",
+            self.name,
+            self.style,
+            self.positions.len()
+        )
     }
 }
 
-impl NaturalNote {
-    fn to_number(&self) -> i8 {
-        match self {
-            NaturalNote::A => 9,
-            NaturalNote::B => 11,
-            NaturalNote::C => 0,
-            NaturalNote::D => 2,
-            NaturalNote::E => 4,
-            NaturalNote::F => 5,
-            NaturalNote::G => 7,
+impl HighlightLayer {
+    fn new(name: &str, style: &HighlightStyle, positions: Vec<(usize, usize)>) -> Self {
+        HighlightLayer {
+            name: name.to_string(),
+            style: style.clone(),
+            positions,
         }
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
-enum TuningType {
-    Open,
-    DropTuning,
-    Standard,
-    Custom,
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+enum Orientation {
+    Horizontal,
+    Vertical,
 }
 
-impl Display for TuningType {
+impl Display for Orientation {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
-            TuningType::Open => write!(f, "Open")?,
-            TuningType::DropTuning => write!(f, "Drop")?,
-            TuningType::Standard => write!(f, "Standard")?,
-            TuningType::Custom => write!(f, "Custom")?,
+            Orientation::Horizontal => write!(f, "Horizontal")?,
+            Orientation::Vertical => write!(f, "Vertical")?,
         }
         Ok(())
     }
 }
 
-impl TuningType {
+impl Orientation {
     fn from_string(input: String) -> Self {
-        let input_uppercase = input.to_uppercase();
-        /*let mut count = 0;
-        let mut indices = Vec::new();
-        for (index, ch) in input.char_indices() {
-            if ch == ' ' {
-                count += 1;
-                indices.push(index);
+        match input.to_uppercase().as_str() {
+            "HORIZONTAL" => Orientation::Horizontal,
+            "VERTICAL" => Orientation::Vertical,
+            _ => {
+                println!("Enter an orientation (Horizontal, Vertical):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                Orientation::from_string(reinput.trim().to_string())
             }
         }
-        if count > 0 {
+    }
+}
 
-        }*/
-        match input_uppercase.as_str() {
-            "OPEN" => TuningType::Open,
-            "DROP" => TuningType::DropTuning,
-            "STANDARD" => TuningType::Standard,
-            "CUSTOM" => TuningType::Custom,
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+enum Handedness {
+    Right,
+    Left,
+}
+
+impl Display for Handedness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Handedness::Right => write!(f, "Right-handed")?,
+            Handedness::Left => write!(f, "Left-handed")?,
+        }
+        Ok(())
+    }
+}
+
+impl Handedness {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "RIGHT" => Handedness::Right,
+            "LEFT" => Handedness::Left,
             _ => {
-                println!("Enter a tuning (e.g., Standard, Drop D, Open G):");
+                println!("Enter a handedness (Right, Left):");
                 let mut reinput = String::new();
                 io::stdin()
                     .read_line(&mut reinput)
                     .expect("Failed to read input");
-                let tuning = TuningType::from_string(reinput.trim().to_string());
-                return tuning;
+                Handedness::from_string(reinput.trim().to_string())
             }
         }
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
-enum InstrumentType {
-    Guitar,
-    Bass,
-    Mandolin,
-    Banjo,
-    Ukelelle,
+// Standard draws the highest string on top of a horizontal neck and the lowest string on the
+// left of a vertical one, the way tab and chord charts are printed.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+enum StringOrder {
+    Standard,
+    Reversed,
 }
 
-impl Display for InstrumentType {
+impl Display for StringOrder {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
-            InstrumentType::Guitar => write!(f, "Guitar")?,
-            InstrumentType::Bass => write!(f, "Bass")?,
-            InstrumentType::Mandolin => write!(f, "Mandolin")?,
-            InstrumentType::Banjo => write!(f, "Banjo")?,
-            InstrumentType::Ukelelle => write!(f, "Ukelelle")?,
+            StringOrder::Standard => write!(f, "Standard")?,
+            StringOrder::Reversed => write!(f, "Reversed")?,
         }
         Ok(())
     }
 }
 
+impl StringOrder {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "STANDARD" => StringOrder::Standard,
+            "REVERSED" => StringOrder::Reversed,
+            _ => {
+                println!("Enter a string order (Standard, Reversed):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                StringOrder::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+}
 
-#[derive(PartialEq, Eq, Clone, Debug)]
-enum Step {
-    Whole,
-    Half,
-    OneAndAHalf,
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct RenderOptions {
+    orientation: Orientation,
+    handedness: Handedness,
+    string_order: StringOrder,
+    fret_window: FretWindow,
+    label_mode: LabelMode,
 }
 
-impl Ord for Step {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.to_number().cmp(&other.to_number())
+impl Display for RenderOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{} {}, {} string order, {}, labelled by {}",
+            self.handedness, self.orientation, self.string_order, self.fret_window, self.label_mode
+        )
     }
 }
 
-impl PartialOrd for Step {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions::new()
     }
 }
 
-impl Step {
-    fn new_whole() -> Self {
-        Step::Whole
+impl RenderOptions {
+    fn new() -> Self {
+        RenderOptions {
+            orientation: Orientation::Horizontal,
+            handedness: Handedness::Right,
+            string_order: StringOrder::Standard,
+            fret_window: FretWindow::Full,
+            label_mode: LabelMode::Names,
+        }
     }
 
-    fn new_half() -> Self {
-        Step::Half
+    // String indices in drawing order: top to bottom when horizontal, left to right when
+    // vertical. A left-handed vertical diagram is mirrored so the lowest string is on the right.
+    fn string_indices(&self, string_count: usize) -> Vec<usize> {
+        let mut indices: Vec<usize> = match self.orientation {
+            Orientation::Horizontal => (0..string_count).rev().collect(),
+            Orientation::Vertical => (0..string_count).collect(),
+        };
+        if self.string_order == StringOrder::Reversed {
+            indices.reverse();
+        }
+        if self.orientation == Orientation::Vertical && self.handedness == Handedness::Left {
+            indices.reverse();
+        }
+        indices
     }
 
-    fn new_one_and_a_half() -> Self {
-        Step::OneAndAHalf
+    // These options cut down to the frets that fit a pane, scrolled offset frets up from the
+    // start of the window.
+    fn pane(&self, instrument: &Instrument, width: usize, height: usize, offset: usize) -> Self {
+        let mut options = self.clone();
+        let (first, last) = options.window_frets(instrument);
+        // Vertical necks spend three rows on the position, string numbers and nut.
+        let capacity = match options.orientation {
+            Orientation::Horizontal => width.saturating_sub(FRET_LABEL_WIDTH) / FRET_CELL_WIDTH,
+            Orientation::Vertical => height.saturating_sub(3),
+        };
+        let start = (first + offset).min(last);
+        options.fret_window = FretWindow::Range(start, last.min(start + capacity.max(1) - 1));
+        options
     }
 
-    fn to_number(self: &Self) -> i8 {
-        match self {
-            Step::Whole => 2,
-            Step::Half => 1,
-            Step::OneAndAHalf => 3,
+    // The first and last fret of the window, however wide the terminal is.
+    fn window_frets(&self, instrument: &Instrument) -> (usize, usize) {
+        match self.fret_window {
+            FretWindow::Full => (0, instrument.fret_count),
+            FretWindow::Range(start, end) => (
+                start.min(end).min(instrument.fret_count),
+                start.max(end).min(instrument.fret_count),
+            ),
+            FretWindow::Fit => instrument
+                .highlighted_frets()
+                .unwrap_or((0, instrument.fret_count)),
+        }
+    }
+
+    // The first and last fret to draw. A horizontal neck is cut short when the terminal is too
+    // narrow for the whole window, keeping the frets nearest its start.
+    fn visible_frets(&self, instrument: &Instrument) -> (usize, usize) {
+        let (first, last) = self.window_frets(instrument);
+        match (&self.orientation, terminal_width()) {
+            (Orientation::Horizontal, Some(width)) => {
+                let capacity = (width.saturating_sub(FRET_LABEL_WIDTH) / FRET_CELL_WIDTH).max(1);
+                (first, last.min(first + capacity - 1))
+            }
+            _ => (first, last),
+        }
+    }
+
+    // Fret indices in drawing order. A left-handed horizontal neck has the nut on the right.
+    fn fret_indices(&self, instrument: &Instrument) -> Vec<usize> {
+        let (first, last) = self.visible_frets(instrument);
+        self.order_frets(first, last)
+    }
+
+    // Frets first to last in the same order, for drawings that aren't cut to the terminal.
+    fn order_frets(&self, first: usize, last: usize) -> Vec<usize> {
+        let frets = first..(last + 1);
+        match (&self.orientation, &self.handedness) {
+            (Orientation::Horizontal, Handedness::Left) => frets.rev().collect(),
+            _ => frets.collect(),
         }
     }
 }
 
-impl Display for Step {
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+enum FretWindow {
+    Full,
+    Range(usize, usize),
+    Fit,
+}
+
+impl Display for FretWindow {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let display_character = match self {
-            Step::Whole => "W".to_string(),
-            Step::Half => "H".to_string(),
-            Step::OneAndAHalf => "3/2".to_string(),
-        };
-        write!(f, "{}", display_character)?;
+        match self {
+            FretWindow::Full => write!(f, "Full neck")?,
+            FretWindow::Range(start, end) => write!(f, "Frets {}-{}", start, end)?,
+            FretWindow::Fit => write!(f, "Fit to highlights")?,
+        }
         Ok(())
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
-struct ScaleDefinition {
-    name: String,
-    steps: Vec<Step>,
+impl FretWindow {
+    fn from_string(input: String) -> Self {
+        let input_uppercase = input.to_uppercase();
+        match input_uppercase.as_str() {
+            "FULL" => return FretWindow::Full,
+            "FIT" => return FretWindow::Fit,
+            _ => (),
+        }
+        if let Some((start, end)) = input_uppercase.split_once('-') {
+            if let (Ok(start), Ok(end)) = (start.trim().parse(), end.trim().parse()) {
+                return FretWindow::Range(start, end);
+            }
+        }
+        println!("Enter a fret window (Full, Fit, or a range e.g., 5-9):");
+        let mut reinput = String::new();
+        io::stdin()
+            .read_line(&mut reinput)
+            .expect("Failed to read input");
+        FretWindow::from_string(reinput.trim().to_string())
+    }
 }
 
-impl ScaleDefinition {
-    fn new_major() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        ScaleDefinition {
-            name: "Major".to_string(),
-            steps,
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+enum LabelMode {
+    Names,
+    Degrees,
+    Intervals,
+    Fingerings,
+}
+
+impl Display for LabelMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            LabelMode::Names => write!(f, "Names")?,
+            LabelMode::Degrees => write!(f, "Degrees")?,
+            LabelMode::Intervals => write!(f, "Intervals")?,
+            LabelMode::Fingerings => write!(f, "Fingerings")?,
         }
+        Ok(())
     }
+}
 
-    fn new_ionian() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        ScaleDefinition {
-            name: "Ionian".to_string(),
-            steps,
+impl LabelMode {
+    fn next(&self) -> Self {
+        match self {
+            LabelMode::Names => LabelMode::Degrees,
+            LabelMode::Degrees => LabelMode::Intervals,
+            LabelMode::Intervals => LabelMode::Fingerings,
+            LabelMode::Fingerings => LabelMode::Names,
         }
     }
 
-    fn new_dorian() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Dorian".to_string(),
-            steps,
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "NAMES" => LabelMode::Names,
+            "DEGREES" => LabelMode::Degrees,
+            "INTERVALS" => LabelMode::Intervals,
+            "FINGERINGS" => LabelMode::Fingerings,
+            _ => {
+                println!("Enter a label mode (Names, Degrees, Intervals, Fingerings):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                LabelMode::from_string(reinput.trim().to_string())
+            }
         }
     }
+}
 
-    fn new_phrygian() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Phrygian".to_string(),
-            steps,
-        }
+// Width of the string number column and of each fret column on a horizontal neck.
+const FRET_LABEL_WIDTH: usize = 2;
+const FRET_CELL_WIDTH: usize = 4;
+
+// None when there's no terminal to measure, in which case nothing is cut to fit.
+fn terminal_width() -> Option<usize> {
+    match crossterm::terminal::size() {
+        Ok((columns, _rows)) if columns > 0 => Some(columns as usize),
+        _ => None,
     }
+}
 
-    fn new_lydian() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        ScaleDefinition {
-            name: "Lydian".to_string(),
-            steps,
+// Columns a line takes up on screen, skipping colour escape sequences.
+fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for character in line.chars() {
+        match (in_escape, character) {
+            (false, '\x1b') => in_escape = true,
+            (false, _) => width += 1,
+            (true, 'm') => in_escape = false,
+            (true, _) => (),
         }
     }
+    width
+}
 
-    fn new_mixolydian() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Mixolydian".to_string(),
-            steps,
+// Lays headed blocks out next to each other. The headers share the first row and shorter
+// blocks are padded at the top so their last lines, where the fret numbers go, share a row too.
+fn join_columns(blocks: &[(String, String)], gap: usize) -> String {
+    let columns: Vec<Vec<&str>> = blocks
+        .iter()
+        .map(|(_header, block)| block.lines().collect())
+        .collect();
+    let height = columns.iter().map(|lines| lines.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = blocks
+        .iter()
+        .zip(&columns)
+        .map(|((header, _block), lines)| {
+            let width = lines.iter().map(|line| visible_width(line)).max();
+            width.unwrap_or(0).max(visible_width(header))
+        })
+        .collect();
+    let mut output = String::new();
+    for row in 0..=height {
+        let mut line = String::new();
+        for (((header, _block), lines), width) in blocks.iter().zip(&columns).zip(&widths) {
+            let text = match (row + lines.len()).checked_sub(height + 1) {
+                _ if row == 0 => header.as_str(),
+                Some(index) => lines[index],
+                None => "",
+            };
+            line.push_str(text);
+            line.push_str(&" ".repeat(width - visible_width(text) + gap));
         }
+        output.push_str(line.trim_end());
+        output.push('\n');
     }
+    output
+}
 
-    fn new_aeolian() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Aeolian".to_string(),
-            steps,
-        }
+// Lays headed blocks out in rows of equal columns, as many to a row as fit in width, reading
+// left to right and then down.
+fn join_grid(blocks: &[(String, String)], width: usize, gap: usize) -> String {
+    let cell_width = blocks
+        .iter()
+        .flat_map(|(header, block)| std::iter::once(header.as_str()).chain(block.lines()))
+        .map(visible_width)
+        .max()
+        .unwrap_or(0);
+    let per_row = ((width + gap) / (cell_width + gap)).max(1);
+    // Padding the headers out to the widest block keeps the columns lined up from row to row.
+    let padded: Vec<(String, String)> = blocks
+        .iter()
+        .map(|(header, block)| {
+            let padding = " ".repeat(cell_width - visible_width(header));
+            (format!("{}{}", header, padding), block.clone())
+        })
+        .collect();
+    let rows: Vec<String> = padded
+        .chunks(per_row)
+        .map(|row| join_columns(row, gap))
+        .collect();
+    rows.join("\n")
+}
+
+// The size of one fret by one string in an SVG diagram, in pixels. Dots, text and gaps scale
+// with it.
+#[derive(PartialEq, Clone, Debug)]
+struct SvgSize {
+    cell_width: f64,
+    cell_height: f64,
+}
+
+impl Display for SvgSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}x{}", self.cell_width, self.cell_height)
     }
+}
 
-    fn new_locrian() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Locrian".to_string(),
-            steps,
+impl SvgSize {
+    fn new() -> Self {
+        SvgSize {
+            cell_width: 40.0,
+            cell_height: 30.0,
         }
     }
 
-    fn new_natural_minor() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Natural Minor".to_string(),
-            steps,
+    // Blank keeps the default; "48" scales it to a cell 48 wide and "48x40" sets both sides.
+    fn from_string(input: String) -> Self {
+        let default = SvgSize::new();
+        let parsed = match input.trim().to_lowercase().split_once('x') {
+            _ if input.trim().is_empty() => Some((default.cell_width, default.cell_height)),
+            Some((width, height)) => width.trim().parse().ok().zip(height.trim().parse().ok()),
+            None => input
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .map(|width| (width, width * default.cell_height / default.cell_width)),
+        };
+        match parsed {
+            Some((cell_width, cell_height)) if cell_width > 0.0 && cell_height > 0.0 => SvgSize {
+                cell_width,
+                cell_height,
+            },
+            _ => {
+                println!(
+                    "Enter a cell size in pixels (e.g., 40x30, blank for {}):",
+                    default
+                );
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                SvgSize::from_string(reinput.trim().to_string())
+            }
         }
     }
 
-    fn new_harmonic_minor() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_one_and_a_half());
-        steps.push(Step::new_half());
-        ScaleDefinition {
-            name: "Harmonic Minor".to_string(),
-            steps,
-        }
+    fn dot_radius(&self) -> f64 {
+        self.cell_width.min(self.cell_height) * 0.4
     }
 
-    fn new_melodic_minor_ascending() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        ScaleDefinition {
-            name: "Melodic Minor Ascending".to_string(),
-            steps,
-        }
+    fn font_size(&self) -> f64 {
+        self.cell_width.min(self.cell_height) * 0.35
     }
+}
 
-    fn new_melodic_minor_descending() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Melodic Minor Descending".to_string(),
-            steps,
-        }
+// A drawing with its own origin at the top left; SvgDiagram::document places it on the page.
+struct SvgDiagram {
+    title: String,
+    width: f64,
+    height: f64,
+    body: String,
+}
+
+impl SvgDiagram {
+    fn hex(rgb: Rgb) -> String {
+        let Rgb(red, green, blue) = rgb;
+        format!("#{:02x}{:02x}{:02x}", red, green, blue)
     }
 
-    fn new_chromatic() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        ScaleDefinition {
-            name: "Chromatic".to_string(),
-            steps,
-        }
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
     }
 
-    fn new_whole_tone() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Whole Tone".to_string(),
-            steps,
-        }
+    fn line(&mut self, (x1, y1): (f64, f64), (x2, y2): (f64, f64), stroke_width: f64) {
+        self.body.push_str(&format!(
+            "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"black\" stroke-width=\"{}\"/>\n",
+            x1, y1, x2, y2, stroke_width
+        ));
     }
 
-    fn new_major_pentatonic() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_one_and_a_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_one_and_a_half());
-        ScaleDefinition {
-            name: "Major Pentatonic".to_string(),
-            steps,
-        }
+    fn text(&mut self, (x, y): (f64, f64), font_size: f64, text: &str) {
+        self.body.push_str(&format!(
+            "  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.1}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+            x,
+            y,
+            font_size,
+            SvgDiagram::escape(text)
+        ));
     }
 
-    fn new_minor_pentatonic() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_one_and_a_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_one_and_a_half());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Minor Pentatonic".to_string(),
-            steps,
-        }
+    fn marker(&mut self, (x, y): (f64, f64), radius: f64) {
+        self.body.push_str(&format!(
+            "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"lightgrey\"/>\n",
+            x, y, radius
+        ));
     }
 
-    fn new_blues() -> Self {
-        let mut steps: Vec<Step> = Vec::new();
-        steps.push(Step::new_one_and_a_half());
-        steps.push(Step::new_whole());
-        steps.push(Step::new_half());
-        steps.push(Step::new_half());
-        steps.push(Step::new_one_and_a_half());
-        steps.push(Step::new_whole());
-        ScaleDefinition {
-            name: "Blues".to_string(),
-            steps,
-        }
+    // A note dot in its color with the label on top, styled like the highlight layer it's on.
+    fn dot(
+        &mut self,
+        center: (f64, f64),
+        size: &SvgSize,
+        label: &str,
+        rgb: Rgb,
+        style: &HighlightStyle,
+    ) {
+        self.body.push_str(&format!(
+            "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"{}\"{}/>\n",
+            center.0,
+            center.1,
+            size.dot_radius(),
+            SvgDiagram::hex(style.adjust(rgb)),
+            style.svg_attributes()
+        ));
+        self.text(center, size.font_size(), label);
     }
-}
 
-impl Display for ScaleDefinition {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let white_rgb = Rgb {
-            0: 255,
-            1: 255,
-            2: 255,
-        };
-        let scale_str: &str = "scale: ";
-        write!(
-            f,
-            "{} {}",
-            self.name.color(white_rgb),
-            scale_str.color(white_rgb)
-        )?;
-        for step in &self.steps {
-            write!(f, "{} ", step.color(white_rgb))?;
+    // The diagram on its own, without a title, for embedding in HTML.
+    fn inline(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.1} {h:.1}\" font-family=\"sans-serif\">\n{}</svg>\n",
+            self.body,
+            w = self.width,
+            h = self.height
+        )
+    }
+
+    // Stacks diagrams down the page, each under its title.
+    fn document(diagrams: &[SvgDiagram], size: &SvgSize) -> String {
+        let title_height = size.cell_height;
+        let width = diagrams.iter().map(|d| d.width).fold(0.0, f64::max) + size.cell_width;
+        let height: f64 = diagrams.iter().map(|d| d.height + title_height * 2.0).sum();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.1} {h:.1}\" font-family=\"sans-serif\">\n",
+            w = width,
+            h = height
+        );
+        svg.push_str(&format!(
+            "<rect width=\"{:.1}\" height=\"{:.1}\" fill=\"white\"/>\n",
+            width, height
+        ));
+        let mut top = 0.0;
+        for diagram in diagrams {
+            svg.push_str(&format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.1}\">{}</text>\n",
+                size.cell_width / 2.0,
+                top + title_height,
+                size.font_size() * 1.5,
+                SvgDiagram::escape(&diagram.title)
+            ));
+            svg.push_str(&format!(
+                "<g transform=\"translate({:.1},{:.1})\">\n{}</g>\n",
+                size.cell_width / 2.0,
+                top + title_height * 1.5,
+                diagram.body
+            ));
+            top += diagram.height + title_height * 2.0;
         }
-        write!(f, "\n")?;
-        Ok(())
+        svg.push_str("</svg>\n");
+        svg
     }
 }
 
 #[derive(PartialEq, Clone, Debug)]
-struct Scale {
-    definition: ScaleDefinition,
-    name: String,
-    notes: Vec<NoteName>,
+enum PaperSize {
+    A4,
+    Letter,
 }
 
-impl Scale {
-    fn new(root_note: &NoteName, definition: &ScaleDefinition) -> Self {
-        let mut notes: Vec<NoteName> = Vec::new();
-        notes.push(root_note.clone());
-        let mut count: usize = 0;
-        for step in &definition.steps {
-            notes.push(NoteName::up_step(&notes[count], &step));
-            count = count + 1;
+impl Display for PaperSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            PaperSize::A4 => write!(f, "A4")?,
+            PaperSize::Letter => write!(f, "Letter")?,
         }
-        Scale {
-            definition: definition.clone(),
-            name: format!("{} {}", &notes[0].get_name(), definition.name),
-            notes,
+        Ok(())
+    }
+}
+
+impl PaperSize {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "A4" => PaperSize::A4,
+            "LETTER" => PaperSize::Letter,
+            _ => {
+                println!("Enter a paper size (A4, Letter):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                PaperSize::from_string(reinput.trim().to_string())
+            }
         }
     }
 
-    fn from_number(key: &NoteName, input: i8) -> Self {
-        match input {
-            0 => Scale::new(&key, &ScaleDefinition::new_major()),
-            1 => Scale::new(&key, &ScaleDefinition::new_ionian()),
-            2 => Scale::new(&key, &ScaleDefinition::new_dorian()),
-            3 => Scale::new(&key, &ScaleDefinition::new_phrygian()),
-            4 => Scale::new(&key, &ScaleDefinition::new_lydian()),
-            5 => Scale::new(&key, &ScaleDefinition::new_mixolydian()),
-            6 => Scale::new(&key, &ScaleDefinition::new_aeolian()),
-            7 => Scale::new(&key, &ScaleDefinition::new_locrian()),
-            8 => Scale::new(&key, &ScaleDefinition::new_natural_minor()),
-            9 => Scale::new(&key, &ScaleDefinition::new_harmonic_minor()),
-            10 => Scale::new(&key, &ScaleDefinition::new_melodic_minor_ascending()),
-            11 => Scale::new(&key, &ScaleDefinition::new_melodic_minor_descending()),
-            12 => Scale::new(&key, &ScaleDefinition::new_chromatic()),
-            13 => Scale::new(&key, &ScaleDefinition::new_whole_tone()),
-            14 => Scale::new(&key, &ScaleDefinition::new_major_pentatonic()),
-            15 => Scale::new(&key, &ScaleDefinition::new_minor_pentatonic()),
-            16 => Scale::new(&key, &ScaleDefinition::new_blues()),
-            _ => panic!("unexpected"),
+    // The CSS @page size keyword.
+    fn css_name(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::Letter => "letter",
         }
     }
+}
 
-    fn from_string(key: &NoteName, input: String) -> Option<Self> {
-        let input: String = input.to_uppercase();
-        match input.as_str() {
-            "MAJOR" => Some(Scale::new(&key, &ScaleDefinition::new_major())),
-            "IONIAN" => Some(Scale::new(&key, &ScaleDefinition::new_ionian())),
-            "DORIAN" => Some(Scale::new(&key, &ScaleDefinition::new_dorian())),
-            "PHRYGIAN" => Some(Scale::new(&key, &ScaleDefinition::new_phrygian())),
-            "LYDIAN" => Some(Scale::new(&key, &ScaleDefinition::new_lydian())),
-            "MIXOLYDIAN" => Some(Scale::new(&key, &ScaleDefinition::new_mixolydian())),
-            "AEOLIAN" => Some(Scale::new(&key, &ScaleDefinition::new_aeolian())),
-            "LOCRIAN" => Some(Scale::new(&key, &ScaleDefinition::new_locrian())),
-            "NATURAL MINOR" => Some(Scale::new(&key, &ScaleDefinition::new_natural_minor())),
-            "HARMONIC MINOR" => Some(Scale::new(&key, &ScaleDefinition::new_harmonic_minor())),
-            "MELODIC MINOR ASCENDING" => {
-                Some(Scale::new(&key, &ScaleDefinition::new_melodic_minor_ascending()))
-            }
-            "MELODIC MINOR DESCENDING" => {
-                Some(Scale::new(&key, &ScaleDefinition::new_melodic_minor_descending()))
+// A printable page for a progression: each chord's name and notes with its chord box next to the
+// neck, kept together on one page.
+struct ChordSheet<'a> {
+    title: String,
+    paper: PaperSize,
+    displays: Vec<&'a DisplayGroup>,
+}
+
+impl ChordSheet<'_> {
+    fn to_html(&self) -> String {
+        let size = SvgSize::new();
+        let mut html = format!(
+            "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>
+@page {{ size: {paper}; margin: 15mm; }}
+body {{ font-family: sans-serif; color: black; background: white; margin: 0; }}
+h1 {{ font-size: 20pt; margin: 0 0 6mm; }}
+h2 {{ font-size: 14pt; margin: 0 0 2mm; }}
+.chord {{ break-inside: avoid; page-break-inside: avoid; margin-bottom: 8mm; }}
+.notes {{ margin: 0 0 3mm; }}
+.diagrams {{ display: flex; align-items: flex-start; gap: 6mm; }}
+.neck {{ flex: 1; min-width: 0; }}
+.neck svg {{ width: 100%; height: auto; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+",
+            title = SvgDiagram::escape(&self.title),
+            paper = self.paper.css_name()
+        );
+        for display in &self.displays {
+            let Some(chord) = &display.chord else {
+                continue;
+            };
+            let notes: Vec<String> = chord.notes.iter().map(|note| note.get_name()).collect();
+            html.push_str("<section class=\"chord\">\n");
+            html.push_str(&format!("<h2>{}</h2>\n", SvgDiagram::escape(&chord.name)));
+            html.push_str(&format!(
+                "<p class=\"notes\">Notes: {}</p>\n",
+                SvgDiagram::escape(&notes.join(" "))
+            ));
+            html.push_str("<div class=\"diagrams\">\n");
+            let mut options = display.render_options.clone();
+            options.orientation = Orientation::Vertical;
+            match &display.voicing {
+                Some(voicing) => {
+                    let chord_box = ChordBoxView::new(&display.instrument, voicing, &options);
+                    html.push_str(&chord_box.to_svg(&chord.short_name, &size).inline());
+                }
+                None => html.push_str("<p>No voicing within reach</p>\n"),
             }
-            "CHROMATIC" => Some(Scale::new(&key, &ScaleDefinition::new_chromatic())),
-            "WHOLE TONE" => Some(Scale::new(&key, &ScaleDefinition::new_whole_tone())),
-            "MAJOR PENTATONIC" => Some(Scale::new(&key, &ScaleDefinition::new_major_pentatonic())),
-            "MINOR PENTATONIC" => Some(Scale::new(&key, &ScaleDefinition::new_minor_pentatonic())),
-            "BLUES" => Some(Scale::new(&key, &ScaleDefinition::new_blues())),
-            _ => None,
+            options.orientation = Orientation::Horizontal;
+            let neck = display.fretboard_view(&options);
+            html.push_str(&format!(
+                "<div class=\"neck\">\n{}</div>\n",
+                neck.to_svg(&chord.short_name, &size).inline()
+            ));
+            html.push_str("</div>\n</section>\n");
         }
+        html.push_str("</body>\n</html>\n");
+        html
     }
 }
 
-impl Display for Scale {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let white_rgb = Rgb {
-            0: 255,
-            1: 255,
-            2: 255,
-        };
-        let scale_str: &str = "scale: ";
-        write!(
-            f,
-            "{} {}",
-            self.name.color(white_rgb),
-            scale_str.color(white_rgb)
-        )?;
-        for note in &self.notes {
-            write!(f, "{} ", note)?;
+// Degrees and intervals are labelled relative to root; fingerings come from a voicing or scale
+// pattern when there is one, otherwise one finger per fret from the start of the window.
+struct FretboardView<'a> {
+    instrument: &'a Instrument,
+    options: &'a RenderOptions,
+    root: NoteName,
+    fingering: Option<Fingering>,
+    pitch_labels: Vec<(usize, usize, NotePitch)>,
+    cursor: Option<(usize, usize)>,
+}
+
+impl<'a> FretboardView<'a> {
+    fn new(
+        instrument: &'a Instrument,
+        options: &'a RenderOptions,
+        root: &NoteName,
+        fingering: Option<Fingering>,
+    ) -> Self {
+        FretboardView {
+            instrument,
+            options,
+            root: root.clone(),
+            fingering,
+            pitch_labels: Vec::new(),
+            cursor: None,
+        }
+    }
+
+    // Shows the (string, fret) position in reverse video, or after a ">" without ANSI.
+    fn with_cursor(mut self, cursor: Option<(usize, usize)>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    // Names a pitch at a position in place of the fretted note, e.g. the harmonic sounding there.
+    fn with_pitch_labels(mut self, pitch_labels: Vec<(usize, usize, NotePitch)>) -> Self {
+        self.pitch_labels = pitch_labels;
+        self
+    }
+
+    // The text and color to draw at a position, or None to leave it blank.
+    fn cell_label(&self, i: usize, j: usize, first_fret: usize) -> Option<(String, Rgb)> {
+        let theme = Theme::current();
+        let note_display = &self.instrument.fretboard[i][j];
+        let note_name = &note_display.note_pitch.note_name;
+        let semitones = NoteName::minus(note_name, self.root.to_number());
+        let degree_rgb = theme.note_rgb(&NoteName::from_number(semitones));
+        if let Some((_, _, pitch)) = self
+            .pitch_labels
+            .iter()
+            .find(|&&(s, f, _)| (s, f) == (i, j))
+        {
+            return Some((pitch.get_name(), theme.note_rgb(&pitch.note_name)));
+        }
+        if self.options.label_mode == LabelMode::Fingerings {
+            if let Some(fingering) = &self.fingering {
+                return match fingering.finger_at(i, j) {
+                    Some(0) => Some(("o".to_string(), degree_rgb)),
+                    Some(finger) => Some((finger.to_string(), degree_rgb)),
+                    None => None,
+                };
+            }
+        }
+        self.instrument.top_layer(i, j)?;
+        match self.options.label_mode {
+            LabelMode::Names => Some((
+                note_display.note_pitch.get_name(),
+                theme.note_rgb(note_name),
+            )),
+            LabelMode::Degrees => Some((
+                Interval::from_semitones(semitones).degree_label(),
+                degree_rgb,
+            )),
+            LabelMode::Intervals => {
+                Some((Interval::quality_name(semitones).to_string(), degree_rgb))
+            }
+            LabelMode::Fingerings => match j {
+                0 => Some(("o".to_string(), degree_rgb)),
+                _ => {
+                    let finger = (j - first_fret.max(1)) % VOICING_SPAN + 1;
+                    Some((finger.to_string(), degree_rgb))
+                }
+            },
+        }
+    }
+
+    fn cell_style(&self, i: usize, j: usize) -> HighlightStyle {
+        match self.instrument.top_layer(i, j) {
+            Some(layer) => layer.style.clone(),
+            None => HighlightStyle::Normal,
+        }
+    }
+
+    // Pads the label to a three column cell and the space after it, underlined when it sits on a
+    // marked fret. Without ANSI the fret numbers carry the marker instead, see fmt_fret_number,
+    // and the style's marker follows the label.
+    fn fmt_cell(&self, f: &mut Formatter<'_>, i: usize, j: usize, first_fret: usize) -> Result {
+        let theme = Theme::current();
+        let style = self.cell_style(i, j);
+        let (text, width) = match self.cell_label(i, j, first_fret) {
+            Some((text, rgb)) => {
+                let painted = theme.paint(&text, style.adjust(rgb)).to_string();
+                match (style.sgr(), theme.uses_ansi(), style.marker()) {
+                    (Some((on, off)), true, _) => (
+                        format!("\x1b[{}m{}\x1b[{}m", on, painted, off),
+                        text.chars().count(),
+                    ),
+                    (_, false, Some(marker)) => {
+                        (format!("{}{}", painted, marker), text.chars().count() + 1)
+                    }
+                    _ => (painted, text.chars().count()),
+                }
+            }
+            None => ("".to_string(), 0),
+        };
+        let padding = " ".repeat(3 - width.min(3));
+        if self.cursor == Some((i, j)) {
+            return match (theme.uses_ansi(), self.cell_label(i, j, first_fret)) {
+                (true, _) => write!(f, "\x1b[7m{}{}\x1b[27m ", text, padding),
+                (false, Some((label, _))) => {
+                    write!(
+                        f,
+                        "{:<4}",
+                        format!(">{}", label).chars().take(3).collect::<String>()
+                    )
+                }
+                (false, None) => write!(f, ">   "),
+            };
+        }
+        // A marker after a three character label takes the place of the space.
+        let space = match theme.uses_ansi() || width < 4 {
+            true => " ",
+            false => "",
+        };
+        match Instrument::is_marked_fret(j) && theme.uses_ansi() {
+            false => write!(f, "{}{}{}", text, padding, space),
+            true => write!(f, "\x1b[4m{}{}\x1b[0m{}", text, padding, space),
+        }
+    }
+
+    // The string and fret drawn at a column and row of the rendered text, for mouse clicks.
+    fn position_at(&self, column: usize, row: usize) -> Option<(usize, usize)> {
+        let instrument = self.instrument;
+        let strings = self.options.string_indices(instrument.string_count);
+        let frets = self.options.fret_indices(instrument);
+        // Skips the "5fr" line drawn above a window that starts past the nut.
+        let row = match frets.iter().min() {
+            Some(&first) if first > 0 => row.checked_sub(1)?,
+            _ => row,
+        };
+        match self.options.orientation {
+            Orientation::Horizontal => {
+                let column = match self.options.handedness {
+                    Handedness::Right => column.checked_sub(FRET_LABEL_WIDTH)?,
+                    Handedness::Left => column,
+                };
+                Some((*strings.get(row)?, *frets.get(column / FRET_CELL_WIDTH)?))
+            }
+            Orientation::Vertical => {
+                // String numbers take the first row and the nut a row under the open strings.
+                let row = row.checked_sub(1)?;
+                let index = match (frets.first(), row) {
+                    (Some(0), 1) => return None,
+                    (Some(0), 2..) => row - 1,
+                    _ => row,
+                };
+                let string = *strings.get(column.checked_sub(3)? / FRET_CELL_WIDTH)?;
+                Some((string, *frets.get(index)?))
+            }
+        }
+    }
+
+    // Writes a fret number padded to width, with a trailing "*" on marked frets when the theme
+    // can't underline them.
+    fn fmt_fret_number(f: &mut Formatter<'_>, fret: usize, width: usize) -> Result {
+        let theme = Theme::current();
+        let number = match Instrument::is_marked_fret(fret) && !theme.uses_ansi() {
+            true => format!("{}*", fret),
+            false => fret.to_string(),
+        };
+        write!(f, "{}", theme.text(format!("{:<width$}", number)))
+    }
+
+    // Labels a window that starts above the nut with its lowest fret, e.g. "5fr".
+    fn fmt_position(&self, f: &mut Formatter<'_>, frets: &[usize]) -> Result {
+        match frets.iter().min() {
+            Some(&first) if first > 0 => {
+                writeln!(f, "{}", Theme::current().text(format!("{}fr", first)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn fmt_horizontal(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        let instrument = self.instrument;
+        let left_handed = self.options.handedness == Handedness::Left;
+        let frets = self.options.fret_indices(instrument);
+        let first_fret = frets.iter().copied().min().unwrap_or(0);
+        self.fmt_position(f, &frets)?;
+        for i in self.options.string_indices(instrument.string_count) {
+            let label = instrument.string_count - i;
+            if !left_handed {
+                write!(f, "{} ", theme.text(label))?;
+            }
+            for &j in &frets {
+                self.fmt_cell(f, i, j, first_fret)?;
+            }
+            if left_handed {
+                write!(f, "{}", theme.text(label))?;
+            }
+            writeln!(f)?;
+        }
+        if !left_handed {
+            write!(f, "  ")?;
+        }
+        for &j in &frets {
+            FretboardView::fmt_fret_number(f, j, FRET_CELL_WIDTH)?;
+        }
+        writeln!(f)
+    }
+
+    fn fmt_vertical(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        let instrument = self.instrument;
+        let strings = self.options.string_indices(instrument.string_count);
+        let frets = self.options.fret_indices(instrument);
+        let first_fret = frets.iter().copied().min().unwrap_or(0);
+        self.fmt_position(f, &frets)?;
+        write!(f, "   ")?;
+        for &i in &strings {
+            write!(f, "{:<4}", theme.text(instrument.string_count - i))?;
+        }
+        writeln!(f)?;
+        for j in frets {
+            write!(f, "{:>2}", theme.text(j))?;
+            match Instrument::is_marked_fret(j) && !theme.uses_ansi() {
+                true => write!(f, "*")?,
+                false => write!(f, " ")?,
+            }
+            for &i in &strings {
+                self.fmt_cell(f, i, j, first_fret)?;
+            }
+            writeln!(f)?;
+            if j == 0 {
+                let nut = "═".repeat(4 * strings.len() - 1);
+                writeln!(f, "   {}", theme.text(nut))?;
+            }
         }
-        write!(f, "\n")?;
         Ok(())
     }
+
+    // The same cells drawn on a neck with a thick nut, inlays on the marked frets (two at each
+    // octave), string numbers and fret numbers. The whole window is drawn, not only what fits
+    // the terminal.
+    fn to_svg(&self, title: &str, size: &SvgSize) -> SvgDiagram {
+        let instrument = self.instrument;
+        let horizontal = self.options.orientation == Orientation::Horizontal;
+        let strings = self.options.string_indices(instrument.string_count);
+        let (first_fret, last_fret) = self.options.window_frets(instrument);
+        let frets = self.options.order_frets(first_fret, last_fret);
+        // Positions are worked out along the neck (frets) and across it (strings), then turned
+        // into x and y for the orientation.
+        let (along, across) = match horizontal {
+            true => (size.cell_width, size.cell_height),
+            false => (size.cell_height, size.cell_width),
+        };
+        let labels_after = horizontal && self.options.handedness == Handedness::Left;
+        let fret_origin = if labels_after { 0.0 } else { along };
+        let string_origin = if horizontal { 0.0 } else { across };
+        let point = |a: f64, c: f64| match horizontal {
+            true => (a, c),
+            false => (c, a),
+        };
+        let fret_center = |k: usize| fret_origin + (k as f64 + 0.5) * along;
+        let string_center = |r: usize| string_origin + (r as f64 + 0.5) * across;
+        let (width, height) = point(
+            along * (frets.len() + 1) as f64,
+            across * (strings.len() + 1) as f64,
+        );
+        let mut diagram = SvgDiagram {
+            title: title.to_string(),
+            width,
+            height,
+            body: String::new(),
+        };
+        let outer = (
+            string_center(0),
+            string_center(strings.len().saturating_sub(1)),
+        );
+        let middle = (outer.0 + outer.1) / 2.0;
+        for (k, &j) in frets.iter().enumerate() {
+            let offsets: &[f64] = match (Instrument::is_marked_fret(j), j % 12) {
+                (false, _) => &[],
+                (true, 0) => &[-1.0, 1.0],
+                (true, _) => &[0.0],
+            };
+            for offset in offsets {
+                let center = point(fret_center(k), middle + offset * across);
+                diagram.marker(center, size.dot_radius() / 2.0);
+            }
+        }
+        // Open strings sit outside the nut, so strings only run over the fretted columns.
+        let fretted: Vec<usize> = (0..frets.len()).filter(|&k| frets[k] > 0).collect();
+        if let (Some(&low), Some(&high)) = (fretted.first(), fretted.last()) {
+            for r in 0..strings.len() {
+                let start = point(fret_origin + low as f64 * along, string_center(r));
+                let end = point(fret_origin + (high + 1) as f64 * along, string_center(r));
+                diagram.line(start, end, 1.0);
+            }
+        }
+        for b in 0..=frets.len() {
+            let before = b.checked_sub(1).map(|k| frets[k]);
+            let stroke = match (before, frets.get(b)) {
+                (Some(0), Some(_)) | (Some(_), Some(0)) => 4.0,
+                (Some(0), None) | (None, Some(0)) => continue,
+                _ => 1.0,
+            };
+            let position = fret_origin + b as f64 * along;
+            diagram.line(point(position, outer.0), point(position, outer.1), stroke);
+        }
+        let label_along = match labels_after {
+            true => fret_origin + (frets.len() as f64 + 0.5) * along,
+            false => along / 2.0,
+        };
+        for (r, &i) in strings.iter().enumerate() {
+            let label = (instrument.string_count - i).to_string();
+            diagram.text(
+                point(label_along, string_center(r)),
+                size.font_size(),
+                &label,
+            );
+        }
+        let number_across = match horizontal {
+            true => string_center(strings.len()),
+            false => across / 2.0,
+        };
+        for (k, &j) in frets.iter().enumerate() {
+            diagram.text(
+                point(fret_center(k), number_across),
+                size.font_size(),
+                &j.to_string(),
+            );
+        }
+        for (r, &i) in strings.iter().enumerate() {
+            for (k, &j) in frets.iter().enumerate() {
+                if let Some((label, rgb)) = self.cell_label(i, j, first_fret) {
+                    let center = point(fret_center(k), string_center(r));
+                    diagram.dot(center, size, &label, rgb, &self.cell_style(i, j));
+                }
+            }
+        }
+        diagram
+    }
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
-enum Accidental {
-    Sharp,
-    Flat,
+impl Display for FretboardView<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.options.orientation {
+            Orientation::Horizontal => self.fmt_horizontal(f),
+            Orientation::Vertical => self.fmt_vertical(f),
+        }
+    }
 }
 
-impl Ord for Accidental {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.to_number().cmp(&other.to_number())
+// One fret (or None for a muted string) per string, lowest string first like
+// Instrument.tuning, with a suggested finger for every fretted note.
+#[derive(PartialEq, Clone, Debug)]
+struct Voicing {
+    frets: Vec<Option<usize>>,
+    fingers: Vec<Option<u8>>,
+}
+
+impl Voicing {
+    // The index finger takes every note on the lowest fretted fret (a barre when there are
+    // several) and the remaining notes get the next fingers from the nut outwards.
+    fn new(frets: &[Option<usize>]) -> Self {
+        let mut fingers: Vec<Option<u8>> = vec![None; frets.len()];
+        let mut fretted: Vec<(usize, usize)> = frets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, fret)| fret.filter(|&fret| fret > 0).map(|fret| (fret, i)))
+            .collect();
+        fretted.sort();
+        let mut finger: u8 = 1;
+        for &(fret, i) in &fretted {
+            if fret != fretted[0].0 {
+                finger += 1;
+            }
+            fingers[i] = Some(finger);
+        }
+        Voicing {
+            frets: frets.to_vec(),
+            fingers,
+        }
+    }
+
+    fn fingering(&self) -> Fingering {
+        let positions = self
+            .frets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, fret)| fret.map(|fret| (i, fret, self.fingers[i].unwrap_or(0))))
+            .collect();
+        Fingering { positions }
+    }
+
+    fn fretted(&self) -> impl Iterator<Item = usize> + '_ {
+        self.frets
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|&fret| fret > 0)
+    }
+
+    // The fret drawn on the top row of a chord box: 1 when the voicing fits under the nut.
+    fn first_fret(&self) -> usize {
+        match self.fretted().max() {
+            Some(highest) if highest > VOICING_SPAN => self.fretted().min().unwrap_or(1),
+            _ => 1,
+        }
+    }
+
+    fn fret_span(&self) -> usize {
+        let first_fret = self.first_fret();
+        let highest = self.fretted().max().unwrap_or(first_fret);
+        (highest + 1 - first_fret).max(VOICING_SPAN)
     }
 }
 
-impl PartialOrd for Accidental {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl Display for Voicing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        for fret in &self.frets {
+            match fret {
+                Some(fret) => write!(f, "{} ", fret)?,
+                None => write!(f, "x ")?,
+            }
+        }
+        Ok(())
     }
 }
 
-impl Display for Accidental {
+// Suggested fingers by string and fret, with 0 for an open string.
+#[derive(PartialEq, Clone, Debug)]
+struct Fingering {
+    positions: Vec<(usize, usize, u8)>,
+}
+
+impl Fingering {
+    fn finger_at(&self, string: usize, fret: usize) -> Option<u8> {
+        self.positions
+            .iter()
+            .find(|&&(i, j, _)| i == string && j == fret)
+            .map(|&(_, _, finger)| finger)
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum PatternSystem {
+    ThreeNotesPerString,
+    TwoNotesPerString,
+    OneOctave,
+}
+
+impl Display for PatternSystem {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
-            Accidental::Sharp => write!(f, "♯")?,
-            Accidental::Flat => write!(f, "♭")?,
+            PatternSystem::ThreeNotesPerString => write!(f, "3 notes per string")?,
+            PatternSystem::TwoNotesPerString => write!(f, "2 notes per string")?,
+            PatternSystem::OneOctave => write!(f, "One octave")?,
         }
         Ok(())
     }
 }
 
-impl Accidental {
-    fn to_number(&self) -> i8 {
+impl PatternSystem {
+    // Notes per string systems only line up into positions for the scale size they are built on.
+    fn note_count(&self) -> Option<usize> {
         match self {
-            Accidental::Sharp => 1,
-            Accidental::Flat => -1,
+            PatternSystem::ThreeNotesPerString => Some(7),
+            PatternSystem::TwoNotesPerString => Some(5),
+            PatternSystem::OneOctave => None,
+        }
+    }
+
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "3NPS" => PatternSystem::ThreeNotesPerString,
+            "2NPS" => PatternSystem::TwoNotesPerString,
+            "OCTAVE" => PatternSystem::OneOctave,
+            _ => {
+                println!("Enter a pattern system (3NPS, 2NPS, Octave):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                PatternSystem::from_string(reinput.trim().to_string())
+            }
         }
     }
 }
 
+// One fingering position of a scale, as (string, fret, finger) with the lowest string first.
 #[derive(PartialEq, Clone, Debug)]
-struct Interval {
-    accidental: Option<Accidental>,
-    interval: i8,
+struct ScalePattern {
+    system: PatternSystem,
+    number: usize,
+    tuning: Vec<NotePitch>,
+    notes: Vec<(usize, usize, u8)>,
 }
 
-impl Interval {
-    fn new(interval: i8, accidental: Option<Accidental>) -> Self {
-        Interval {
-            interval,
-            accidental,
+impl ScalePattern {
+    fn first_fret(&self) -> usize {
+        self.notes
+            .iter()
+            .map(|&(_, fret, _)| fret)
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn last_fret(&self) -> usize {
+        self.notes
+            .iter()
+            .map(|&(_, fret, _)| fret)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn fingering(&self) -> Fingering {
+        Fingering {
+            positions: self.notes.clone(),
         }
     }
+
+    // Fingers for the notes on one string: the index on the lowest fretted note (or the first
+    // fret after an open string), the little finger on a stretch of three frets or more, and the
+    // fingers in between by distance.
+    fn string_fingers(frets: &[usize]) -> Vec<u8> {
+        let (Some(&lowest), Some(&highest)) = (frets.iter().min(), frets.iter().max()) else {
+            return Vec::new();
+        };
+        let lowest = lowest.max(1);
+        frets
+            .iter()
+            .map(|&fret| {
+                match (
+                    fret,
+                    fret.saturating_sub(lowest),
+                    highest.saturating_sub(lowest),
+                ) {
+                    (0, _, _) => 0,
+                    (_, 0, _) => 1,
+                    (_, distance, span) if distance == span && span >= 3 => 4,
+                    (_, distance, _) => (distance + 1).min(4) as u8,
+                }
+            })
+            .collect()
+    }
 }
 
-impl Display for Interval {
+impl Display for ScalePattern {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        match self.accidental {
-            Some(Accidental::Sharp) => write!(f, "♯{}", self.interval)?,
-            Some(Accidental::Flat) => write!(f, "♭{}", self.interval)?,
-            None => write!(f, "{}", self.interval)?,
+        let theme = Theme::current();
+        writeln!(
+            f,
+            "{}",
+            theme.text(format!(
+                "{} pattern {}: frets {}-{}",
+                self.system,
+                self.number,
+                self.first_fret(),
+                self.last_fret()
+            ))
+        )?;
+        for (i, open_note) in self.tuning.iter().enumerate().rev() {
+            let notes: Vec<String> = self
+                .notes
+                .iter()
+                .filter(|&&(string, _, _)| string == i)
+                .map(|&(_, fret, finger)| format!("{}({})", fret, finger))
+                .collect();
+            if !notes.is_empty() {
+                let line = format!("{:<4}{}", open_note.get_name(), notes.join(" "));
+                writeln!(f, "\t{}", theme.text(line))?;
+            }
         }
         Ok(())
     }
 }
 
 #[derive(PartialEq, Clone, Debug)]
-struct ChordDefinition {
-    name: String,
-    naming_convention: String,
-    intervals: Vec<Interval>,
+enum RunDirection {
+    Up,
+    Down,
+    UpAndDown,
 }
 
-impl Display for ChordDefinition {
+impl Display for RunDirection {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let white_rgb = Rgb {
-            0: 255,
-            1: 255,
-            2: 255,
-        };
-        let chord_str: &str = "chord: ";
-        write!(
-            f,
-            "{} {}",
-            self.name.color(white_rgb),
-            chord_str.color(white_rgb)
-        )?;
-        for interval in &self.intervals {
-            write!(f, "{} ", interval.color(white_rgb))?;
+        match self {
+            RunDirection::Up => write!(f, "Ascending")?,
+            RunDirection::Down => write!(f, "Descending")?,
+            RunDirection::UpAndDown => write!(f, "Ascending and descending")?,
         }
-        write!(f, "\n")?;
         Ok(())
     }
 }
 
-impl ChordDefinition {
-    fn new_major() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, None));
-        ChordDefinition {
-            name: "Major".to_string(),
-            naming_convention: "".to_string(),
-            intervals,
+impl RunDirection {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "UP" => RunDirection::Up,
+            "DOWN" => RunDirection::Down,
+            "BOTH" => RunDirection::UpAndDown,
+            _ => {
+                println!("Enter a direction (Up, Down, Both):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                RunDirection::from_string(reinput.trim().to_string())
+            }
         }
     }
 
-    fn new_minor() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, Some(Accidental::Flat)));
-        intervals.push(Interval::new(5, None));
-        ChordDefinition {
-            name: "Minor".to_string(),
-            naming_convention: "m".to_string(),
-            intervals,
+    // The items as given, reversed, or there and back without playing the turn twice.
+    fn order<T: Clone>(&self, items: &[T]) -> Vec<T> {
+        let descending: Vec<T> = items.iter().rev().cloned().collect();
+        match self {
+            RunDirection::Up => items.to_vec(),
+            RunDirection::Down => descending,
+            RunDirection::UpAndDown => items
+                .iter()
+                .chain(descending.iter().skip(1))
+                .cloned()
+                .collect(),
         }
     }
+}
 
-    fn new_diminished() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, Some(Accidental::Flat)));
-        intervals.push(Interval::new(5, Some(Accidental::Flat)));
-        ChordDefinition {
-            name: "Diminished".to_string(),
-            naming_convention: "dim".to_string(),
-            intervals,
+// One beat of tab: a fret or nothing on each string, lowest string first, or a bar line.
+#[derive(PartialEq, Clone, Debug)]
+enum TabColumn {
+    Frets(Vec<Option<usize>>),
+    Bar,
+}
+
+// Plain ASCII tablature with the highest string on the top line, each named from the tuning.
+#[derive(PartialEq, Clone, Debug)]
+struct Tab {
+    tuning: Vec<NotePitch>,
+    columns: Vec<TabColumn>,
+}
+
+impl Tab {
+    fn new(tuning: &[NotePitch]) -> Self {
+        Tab {
+            tuning: tuning.to_vec(),
+            columns: Vec::new(),
         }
     }
 
-    fn new_augmented() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, Some(Accidental::Sharp)));
-        ChordDefinition {
-            name: "Augmented".to_string(),
-            naming_convention: "aug".to_string(),
-            intervals,
+    // Single notes as (string, fret), given low to high. Up and down turns at the top note
+    // without repeating it. An empty run adds nothing.
+    fn push_run(&mut self, notes: &[(usize, usize)], direction: &RunDirection) {
+        if notes.is_empty() {
+            return;
+        }
+        for (string, fret) in direction.order(notes) {
+            let mut frets = vec![None; self.tuning.len()];
+            frets[string] = Some(fret);
+            self.columns.push(TabColumn::Frets(frets));
         }
     }
 
-    fn new_suspended_second() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(2, None));
-        intervals.push(Interval::new(5, None));
-        ChordDefinition {
-            name: "Suspended 2".to_string(),
-            naming_convention: "sus2".to_string(),
-            intervals,
+    fn push_chord(&mut self, frets: &[Option<usize>]) {
+        self.columns.push(TabColumn::Frets(frets.to_vec()));
+    }
+
+    fn push_bar(&mut self) {
+        self.columns.push(TabColumn::Bar);
+    }
+
+    // Each column padded to its widest fret number and followed by a dash, for string i.
+    fn cell(column: &TabColumn, i: usize) -> String {
+        match column {
+            TabColumn::Bar => "|-".to_string(),
+            TabColumn::Frets(frets) => {
+                let width = frets
+                    .iter()
+                    .flatten()
+                    .map(|fret| fret.to_string().len())
+                    .max()
+                    .unwrap_or(1);
+                let text = match frets[i] {
+                    Some(fret) => fret.to_string(),
+                    None => "".to_string(),
+                };
+                format!("{:-<width$}-", text)
+            }
         }
     }
 
-    fn new_suspended_four() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(4, None));
-        intervals.push(Interval::new(5, None));
-        ChordDefinition {
-            name: "Suspended 4".to_string(),
-            naming_convention: "sus4".to_string(),
-            intervals,
+    // Breaks into systems of lines no wider than width, leaving out bar lines that would start
+    // or end a system.
+    fn render(&self, width: usize) -> String {
+        let names: Vec<String> = self
+            .tuning
+            .iter()
+            .map(|pitch| Theme::to_ascii(&pitch.note_name.get_name()))
+            .collect();
+        let name_width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+        let prefix_width = name_width + 2;
+        let mut systems: Vec<Vec<&TabColumn>> = vec![Vec::new()];
+        let mut used = prefix_width + 1;
+        for column in &self.columns {
+            let cell_width = Tab::cell(column, 0).len();
+            let current = systems.last_mut().expect("always one system");
+            let fits = used + cell_width <= width || current.is_empty();
+            match (column, fits) {
+                (TabColumn::Bar, false) => continue,
+                (TabColumn::Bar, true) if current.is_empty() => continue,
+                (_, false) => {
+                    systems.push(vec![column]);
+                    used = prefix_width + 1 + cell_width;
+                }
+                (_, true) => {
+                    current.push(column);
+                    used += cell_width;
+                }
+            }
+        }
+        let mut output = String::new();
+        for (index, system) in systems.iter_mut().enumerate() {
+            if system.last() == Some(&&TabColumn::Bar) {
+                system.pop();
+            }
+            if index > 0 {
+                output.push('\n');
+            }
+            for i in (0..self.tuning.len()).rev() {
+                output.push_str(&format!("{:<name_width$}|-", names[i]));
+                for column in system.iter() {
+                    output.push_str(&Tab::cell(column, i));
+                }
+                output.push_str("|\n");
+            }
         }
+        output
     }
+}
 
-    fn new_power() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(5, None));
-        ChordDefinition {
-            name: "Power".to_string(),
-            naming_convention: "5".to_string(),
-            intervals,
+// Partials up to the eighth are practical to sound as natural harmonics.
+const HIGHEST_PARTIAL: u8 = 8;
+
+// A natural harmonic node: touching the string lightly over fret sounds the partial, which may
+// sit off equal temperament by cents.
+#[derive(PartialEq, Clone, Debug)]
+struct Harmonic {
+    string: usize,
+    partial: u8,
+    fret: f64,
+    pitch: NotePitch,
+    cents: f64,
+}
+
+impl Display for Harmonic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{:.1} {}",
+            self.fret,
+            Theme::printable(&self.pitch.get_name())
+        )?;
+        if self.cents.abs() >= 1.0 {
+            write!(f, " ({:+.0}c)", self.cents)?;
         }
+        Ok(())
     }
+}
 
-    fn new_major_seven() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(7, None));
-        ChordDefinition {
-            name: "Major 7".to_string(),
-            naming_convention: "maj7".to_string(),
-            intervals,
-        }
+impl Harmonic {
+    // The nearest fret, for drawing on the fretboard.
+    fn nearest_fret(&self) -> usize {
+        self.fret.round() as usize
     }
+}
 
-    fn new_minor_seven() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, Some(Accidental::Flat)));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        ChordDefinition {
-            name: "Minor 7".to_string(),
-            naming_convention: "m7".to_string(),
-            intervals,
+struct ChordBoxView<'a> {
+    instrument: &'a Instrument,
+    voicing: &'a Voicing,
+    options: &'a RenderOptions,
+}
+
+impl<'a> ChordBoxView<'a> {
+    fn new(instrument: &'a Instrument, voicing: &'a Voicing, options: &'a RenderOptions) -> Self {
+        ChordBoxView {
+            instrument,
+            voicing,
+            options,
         }
     }
 
-    fn new_dominant_seven() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        ChordDefinition {
-            name: "Dominant 7".to_string(),
-            naming_convention: "7".to_string(),
-            intervals,
+    fn fmt_line(
+        f: &mut Formatter<'_>,
+        count: usize,
+        left: &str,
+        middle: &str,
+        right: &str,
+    ) -> Result {
+        let fill = match left {
+            "╒" => "══",
+            _ => "──",
+        };
+        let mut line = left.to_string();
+        for k in 1..count {
+            line.push_str(fill);
+            line.push_str(if k + 1 == count { right } else { middle });
         }
+        writeln!(f, "    {}", Theme::current().text(line))
     }
 
-    fn new_minor_major_seven() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, Some(Accidental::Flat)));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(7, None));
-        ChordDefinition {
-            name: "Minor Major 7".to_string(),
-            naming_convention: "m(Maj7)".to_string(),
-            intervals,
+    // The box as a drawing: x and o over the strings, fingers on dots in the note colors and the
+    // open notes underneath.
+    fn to_svg(&self, title: &str, size: &SvgSize) -> SvgDiagram {
+        let strings = self.options.string_indices(self.instrument.string_count);
+        let first_fret = self.voicing.first_fret();
+        let rows = self.voicing.fret_span();
+        let (cell_width, cell_height) = (size.cell_width, size.cell_height);
+        let x = |k: usize| cell_width * (k as f64 + 1.5);
+        let fret_line = |row: usize| cell_height * (row as f64 + 1.0);
+        let mut diagram = SvgDiagram {
+            title: title.to_string(),
+            width: cell_width * (strings.len() + 2) as f64,
+            height: cell_height * (rows + 2) as f64,
+            body: String::new(),
+        };
+        let last = strings.len().saturating_sub(1);
+        for row in 0..=rows {
+            let stroke = match (row, first_fret) {
+                (0, 1) => 4.0,
+                _ => 1.0,
+            };
+            diagram.line((x(0), fret_line(row)), (x(last), fret_line(row)), stroke);
+        }
+        if first_fret > 1 {
+            let label = format!("{}fr", first_fret);
+            let center = (cell_width / 2.0, fret_line(0) + cell_height / 2.0);
+            diagram.text(center, size.font_size(), &label);
+        }
+        for (k, &i) in strings.iter().enumerate() {
+            diagram.line((x(k), fret_line(0)), (x(k), fret_line(rows)), 1.0);
+            let above = (x(k), cell_height / 2.0);
+            match (self.voicing.frets[i], self.voicing.fingers[i]) {
+                (None, _) => diagram.text(above, size.font_size(), "x"),
+                (Some(0), _) => diagram.text(above, size.font_size(), "o"),
+                (Some(played), finger) => {
+                    let note_name = &self.instrument.fretboard[i][played].note_pitch.note_name;
+                    let rgb = Theme::current().note_rgb(note_name);
+                    let center = (x(k), fret_line(played - first_fret) + cell_height / 2.0);
+                    let label = finger.map(|finger| finger.to_string()).unwrap_or_default();
+                    diagram.dot(center, size, &label, rgb, &HighlightStyle::Normal);
+                }
+            }
+            let below = (x(k), fret_line(rows) + cell_height / 2.0);
+            let open_note = self.instrument.tuning[i].note_name.get_name();
+            diagram.text(below, size.font_size(), &open_note);
         }
+        diagram
     }
+}
 
-    fn new_six() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(6, None));
-        ChordDefinition {
-            name: "6".to_string(),
-            naming_convention: "6".to_string(),
-            intervals,
+impl Display for ChordBoxView<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        let strings = self.options.string_indices(self.instrument.string_count);
+        let count = strings.len();
+        let first_fret = self.voicing.first_fret();
+        let rows = self.voicing.fret_span();
+        write!(f, "    ")?;
+        for &i in &strings {
+            let marker = match self.voicing.frets[i] {
+                None => "x",
+                Some(0) => "o",
+                Some(_) => " ",
+            };
+            write!(f, "{:<3}", theme.text(marker))?;
+        }
+        writeln!(f)?;
+        match first_fret {
+            1 => ChordBoxView::fmt_line(f, count, "╒", "╤", "╕")?,
+            _ => ChordBoxView::fmt_line(f, count, "┌", "┬", "┐")?,
+        }
+        for row in 0..rows {
+            let fret = first_fret + row;
+            let label = match (row, first_fret) {
+                (0, 2..) => format!("{}fr", first_fret),
+                _ => "".to_string(),
+            };
+            write!(f, "{:<4}", theme.text(label))?;
+            for (k, &i) in strings.iter().enumerate() {
+                match (self.voicing.frets[i], self.voicing.fingers[i]) {
+                    (Some(played), Some(finger)) if played == fret => {
+                        let note_name = &self.instrument.fretboard[i][played].note_pitch.note_name;
+                        write!(f, "{}", theme.paint(finger, theme.note_rgb(note_name)))?
+                    }
+                    _ => write!(f, "{}", theme.text("│"))?,
+                }
+                if k + 1 < count {
+                    write!(f, "  ")?;
+                }
+            }
+            writeln!(f)?;
+            match row + 1 == rows {
+                false => ChordBoxView::fmt_line(f, count, "├", "┼", "┤")?,
+                true => ChordBoxView::fmt_line(f, count, "└", "┴", "┘")?,
+            }
+        }
+        write!(f, "    ")?;
+        for &i in &strings {
+            let note_name = &self.instrument.tuning[i].note_name;
+            let padding = 3 - note_name.get_name().chars().count();
+            write!(f, "{}{}", note_name, " ".repeat(padding))?;
         }
+        writeln!(f)
     }
+}
 
-    fn new_minor_six() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, Some(Accidental::Flat)));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(6, None));
-        ChordDefinition {
-            name: "Minor 6".to_string(),
-            naming_convention: "m6".to_string(),
-            intervals,
+// Chosen once at startup from the environment and changeable from the menu. Display impls read
+// it through Theme::current so every note, chord and scale follows the same palette.
+static THEME: RwLock<Theme> = RwLock::new(Theme::Dark);
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+enum Theme {
+    Dark,
+    Light,
+    Colorblind,
+    Monochrome,
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Theme::Dark => write!(f, "Dark")?,
+            Theme::Light => write!(f, "Light")?,
+            Theme::Colorblind => write!(f, "Colorblind")?,
+            Theme::Monochrome => write!(f, "Monochrome")?,
         }
+        Ok(())
     }
-    fn new_nine() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        intervals.push(Interval::new(9, None));
-        ChordDefinition {
-            name: "9".to_string(),
-            naming_convention: "9".to_string(),
-            intervals,
+}
+
+impl Theme {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "DARK" => Theme::Dark,
+            "LIGHT" => Theme::Light,
+            "COLORBLIND" => Theme::Colorblind,
+            "MONOCHROME" => Theme::Monochrome,
+            _ => {
+                println!("Enter a theme (Dark, Light, Colorblind, Monochrome):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                Theme::from_string(reinput.trim().to_string())
+            }
         }
     }
 
-    fn new_minor_nine() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, Some(Accidental::Flat)));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        intervals.push(Interval::new(9, None));
-        ChordDefinition {
-            name: "Minor 9".to_string(),
-            naming_convention: "m9".to_string(),
-            intervals,
+    // Monochrome when NO_COLOR is set (https://no-color.org) or stdout isn't a terminal.
+    fn detect() -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        match no_color || !io::stdout().is_terminal() {
+            true => Theme::Monochrome,
+            false => Theme::Dark,
         }
     }
 
-    fn new_add_nine() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(9, None));
-        ChordDefinition {
-            name: "Add 9".to_string(),
-            naming_convention: "add9".to_string(),
-            intervals,
+    fn current() -> Self {
+        match THEME.read() {
+            Ok(theme) => theme.clone(),
+            Err(_) => Theme::Dark,
         }
     }
 
-    fn new_seven_suspended_four() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(4, None));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        ChordDefinition {
-            name: "7 Suspended 4".to_string(),
-            naming_convention: "7sus4".to_string(),
-            intervals,
+    fn set(theme: &Theme) {
+        if let Ok(mut current) = THEME.write() {
+            *current = theme.clone();
         }
     }
 
-    fn new_dimished_seven() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, Some(Accidental::Flat)));
-        intervals.push(Interval::new(5, Some(Accidental::Flat)));
-        intervals.push(Interval::new(6, None));
-        ChordDefinition {
-            name: "Diminished 7".to_string(),
-            naming_convention: "dim7".to_string(),
-            intervals,
-        }
+    // Monochrome output is plain ASCII with no escape codes at all.
+    fn uses_ansi(&self) -> bool {
+        *self != Theme::Monochrome
     }
 
-    fn new_half_diminished() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, Some(Accidental::Flat)));
-        intervals.push(Interval::new(5, Some(Accidental::Flat)));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        ChordDefinition {
-            name: "Half Diminished".to_string(),
-            naming_convention: "7♭5".to_string(),
-            intervals,
+    fn text_rgb(&self) -> Rgb {
+        match self {
+            Theme::Light => Rgb(0, 0, 0),
+            _ => Rgb(255, 255, 255),
         }
     }
 
-    fn new_plus_seven() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, Some(Accidental::Sharp)));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        ChordDefinition {
-            name: "Plus 7".to_string(),
-            naming_convention: "+7".to_string(),
-            intervals,
+    // Dark keeps the original NoteName::to_rgb palette and Light darkens it to stay readable on
+    // a white background. Colorblind uses the Okabe-Ito palette, one hue per natural note, with
+    // sharps in a darker shade of the note below.
+    fn note_rgb(&self, note_name: &NoteName) -> Rgb {
+        match self {
+            Theme::Dark | Theme::Monochrome => note_name.to_rgb(),
+            Theme::Light => {
+                let Rgb(red, green, blue) = note_name.to_rgb();
+                Rgb(
+                    red / 2 + red / 8,
+                    green / 2 + green / 8,
+                    blue / 2 + blue / 8,
+                )
+            }
+            Theme::Colorblind => {
+                let note_name = NoteName::from_number(note_name.to_number());
+                let Rgb(red, green, blue) = match note_name.natural_note {
+                    NaturalNote::C => Rgb(213, 94, 0),
+                    NaturalNote::D => Rgb(230, 159, 0),
+                    NaturalNote::E => Rgb(240, 228, 66),
+                    NaturalNote::F => Rgb(0, 158, 115),
+                    NaturalNote::G => Rgb(86, 180, 233),
+                    NaturalNote::A => Rgb(0, 114, 178),
+                    NaturalNote::B => Rgb(204, 121, 167),
+                };
+                match note_name.accidental {
+                    Some(_) => Rgb(
+                        red / 2 + red / 8,
+                        green / 2 + green / 8,
+                        blue / 2 + blue / 8,
+                    ),
+                    None => Rgb(red, green, blue),
+                }
+            }
         }
     }
 
-    fn new_minor_eleven() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, Some(Accidental::Flat)));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        intervals.push(Interval::new(9, None));
-        intervals.push(Interval::new(11, None));
-        ChordDefinition {
-            name: "Minor 11".to_string(),
-            naming_convention: "m11".to_string(),
-            intervals,
+    fn paint<T: Display>(&self, value: T, rgb: Rgb) -> Painted<T> {
+        Painted {
+            value,
+            rgb: match self.uses_ansi() {
+                true => Some(rgb),
+                false => None,
+            },
+            ascii: !self.uses_ansi(),
         }
     }
 
-    fn new_augmented_major_seven() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, Some(Accidental::Sharp)));
-        intervals.push(Interval::new(7, None));
-        ChordDefinition {
-            name: "Augmented Major 7".to_string(),
-            naming_convention: "Maj7♯5".to_string(),
-            intervals,
-        }
+    fn text<T: Display>(&self, value: T) -> Painted<T> {
+        self.paint(value, self.text_rgb())
     }
 
-    fn new_dominant_seven_flat_nine() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, None));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        intervals.push(Interval::new(9, Some(Accidental::Flat)));
-        ChordDefinition {
-            name: "Dominant 7 Flat 9".to_string(),
-            naming_convention: "7♭9".to_string(),
-            intervals,
+    // Text printed straight to the terminal, in ASCII when the current theme is.
+    fn printable(text: &str) -> String {
+        match Theme::current().uses_ansi() {
+            true => text.to_string(),
+            false => Theme::to_ascii(text),
         }
     }
 
-    fn new_altered_dominant_seven() -> Self {
-        let mut intervals: Vec<Interval> = Vec::new();
-        intervals.push(Interval::new(1, None));
-        intervals.push(Interval::new(3, None));
-        intervals.push(Interval::new(5, Some(Accidental::Sharp)));
-        intervals.push(Interval::new(7, Some(Accidental::Flat)));
-        intervals.push(Interval::new(9, Some(Accidental::Sharp)));
-        ChordDefinition {
-            name: "Altered Dominant 7".to_string(),
-            naming_convention: "7♯5♯9".to_string(),
-            intervals,
-        }
+    fn to_ascii(text: &str) -> String {
+        text.chars()
+            .map(|character| match character {
+                '♯' => '#',
+                '♭' => 'b',
+                '═' => '=',
+                '─' => '-',
+                '│' => '|',
+                '\u{2002}' => ' ',
+                '╒' | '╤' | '╕' | '┌' | '┬' | '┐' | '├' | '┼' | '┤' | '└' | '┴' | '┘' => {
+                    '+'
+                }
+                _ => character,
+            })
+            .collect()
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
-struct Chord {
-    definition: ChordDefinition,
-    notes: Vec<NoteName>,
-    name: String,
-    short_name: String,
+// A value drawn in a theme's color. Width and alignment flags apply to the text itself, not
+// the escape codes around it.
+struct Painted<T> {
+    value: T,
+    rgb: Option<Rgb>,
+    ascii: bool,
 }
 
-impl Display for Chord {
+impl<T: Display> Display for Painted<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let white_rgb = Rgb {
-            0: 255,
-            1: 255,
-            2: 255,
+        let text = match self.ascii {
+            true => Theme::to_ascii(&self.value.to_string()),
+            false => self.value.to_string(),
         };
-        let open_bracket: &str = "(";
-        let closed_bracket: &str = ")";
-        let chord_str: &str = "chord: ";
-        write!(
-            f,
-            "{} {}{}{} {}",
-            self.name.color(white_rgb),
-            open_bracket.color(white_rgb),
-            self.short_name.color(white_rgb),
-            closed_bracket.color(white_rgb),
-            chord_str.color(white_rgb)
-        )?;
-        for note in &self.notes {
-            write!(f, "{} ", note)?;
+        match self.rgb {
+            Some(rgb) => Display::fmt(&text.color(rgb), f),
+            None => Display::fmt(&text, f),
         }
-        write!(f, "\n")?;
-        Ok(())
     }
 }
 
-impl Chord {
-    fn new(root_note: &NoteName, definition: &ChordDefinition) -> Self {
-        let scale = Scale::new(root_note, &ScaleDefinition::new_major());
-        let mut notes: Vec<NoteName> = Vec::new();
-        let scale_length = scale.notes.len();
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+struct NotePitch {
+    note_name: NoteName,
+    octave: i8,
+}
 
-        for interval in &definition.intervals {
-            let interval_value = interval.interval as usize;
+impl From<NotePitch> for String {
+    fn from(note_pitch: NotePitch) -> String {
+        Theme::to_ascii(&note_pitch.get_name())
+    }
+}
 
-            let i: usize = if interval_value > scale_length {
-                interval_value % scale_length
-            } else {
-                interval_value - 1
-            };
+impl TryFrom<String> for NotePitch {
+    type Error = String;
 
-            let base_note = &scale.notes[i];
-            let note = match interval.accidental {
-                None => base_note.clone(),
-                Some(Accidental::Flat) => NoteName::down_step(base_note, &Step::new_half()),
-                Some(Accidental::Sharp) => NoteName::up_step(base_note, &Step::new_half()),
-            };
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        NotePitch::parse(&value).ok_or(format!("{} is not a pitch", value))
+    }
+}
 
-            notes.push(note);
+impl Display for NotePitch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        let rgb = theme.note_rgb(&self.note_name);
+        match (&self.note_name.accidental, theme.uses_ansi()) {
+            (Some(_accidental), _) => write!(f, "{}", theme.paint(self.get_name(), rgb)),
+            (None, true) => write!(f, "\u{2002}{}", theme.paint(self.get_name(), rgb)),
+            (None, false) => write!(f, " {}", theme.paint(self.get_name(), rgb)),
         }
+    }
+}
 
-        Chord {
-            definition: definition.clone(),
-            notes,
-            name: format!("{} {}", NoteName::get_name(root_note), definition.name),
-            short_name: format!(
-                "{}{}",
-                NoteName::get_name(root_note),
-                definition.naming_convention
-            ),
+impl NotePitch {
+    fn new(natural_note: &NaturalNote, accidental: &Option<Accidental>, octave: i8) -> Self {
+        NotePitch {
+            note_name: NoteName::new(natural_note, accidental),
+            octave,
         }
     }
 
-    fn from_string(key: &NoteName, input: String) -> Option<Self> {
-        let input_uppercase: String = input.to_uppercase();
-        match input_uppercase.as_str() {
-            "MAJOR" => Some(Chord::new(&key, &ChordDefinition::new_major())),
-            "MINOR" => Some(Chord::new(&key, &ChordDefinition::new_minor())),
-            "DIMINISHED" => Some(Chord::new(&key, &ChordDefinition::new_diminished())),
-            "AUGMENTED" => Some(Chord::new(&key, &ChordDefinition::new_augmented())),
-            "SUSPENDED TWO" => Some(Chord::new(&key, &ChordDefinition::new_suspended_second())),
-            "SUSPENDED FOUR" => Some(Chord::new(&key, &ChordDefinition::new_suspended_four())),
-            "POWER" => Some(Chord::new(&key, &ChordDefinition::new_power())),
-            "MAJOR SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_major_seven())),
-            "MINOR SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_minor_seven())),
-            "DOMINANT SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_dominant_seven())),
-            "MINOR MAJOR SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_minor_major_seven())),
-            "SIX" => Some(Chord::new(&key, &ChordDefinition::new_six())),
-            "MINOR SIX" => Some(Chord::new(&key, &ChordDefinition::new_minor_six())),
-            "NINE" => Some(Chord::new(&key, &ChordDefinition::new_nine())),
-            "MINOR NINE" => Some(Chord::new(&key, &ChordDefinition::new_minor_nine())),
-            "ADD NINE" => Some(Chord::new(&key, &ChordDefinition::new_add_nine())),
-            "SEVEN SUSPENDED FOUR" => Some(Chord::new(&key, &ChordDefinition::new_seven_suspended_four())),
-            "DIMINISHED SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_dimished_seven())),
-            "HALF DIMINISHED" => Some(Chord::new(&key, &ChordDefinition::new_half_diminished())),
-            "PLUS SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_plus_seven())),
-            "MINOR ELEVEN" => Some(Chord::new(&key, &ChordDefinition::new_minor_eleven())),
-            "AUGMENTED MAJOR SEVEN" => {
-                Some(Chord::new(&key, &ChordDefinition::new_augmented_major_seven()))
-            }
-            "DOMINANT SEVEN FLAT NINE" => {
-                Some(Chord::new(&key, &ChordDefinition::new_dominant_seven_flat_nine()))
-            }
-            "ALTERED DOMINANT SEVEN" => {
-                Some(Chord::new(&key, &ChordDefinition::new_altered_dominant_seven()))
-            }
-            _ => None,
+    fn get_name(self: &Self) -> String {
+        format!("{}{}", self.note_name.get_name(), self.octave)
+    }
+
+    fn from_note_name(note_name: &NoteName, octave: i8) -> Self {
+        NotePitch {
+            note_name: note_name.clone(),
+            octave,
         }
     }
 
-    fn from_number(key: &NoteName, input: i8) -> Self {
-        match input {
-            0 => Chord::new(&key, &ChordDefinition::new_major()),
-            1 => Chord::new(&key, &ChordDefinition::new_minor()),
-            2 => Chord::new(&key, &ChordDefinition::new_diminished()),
-            3 => Chord::new(&key, &ChordDefinition::new_augmented()),
-            4 => Chord::new(&key, &ChordDefinition::new_suspended_second()),
-            5 => Chord::new(&key, &ChordDefinition::new_suspended_four()),
-            6 => Chord::new(&key, &ChordDefinition::new_power()),
-            7 => Chord::new(&key, &ChordDefinition::new_major_seven()),
-            8 => Chord::new(&key, &ChordDefinition::new_minor_seven()),
-            9 => Chord::new(&key, &ChordDefinition::new_dominant_seven()),
-            10 => Chord::new(&key, &ChordDefinition::new_minor_major_seven()),
-            11 => Chord::new(&key, &ChordDefinition::new_six()),
-            12 => Chord::new(&key, &ChordDefinition::new_minor_six()),
-            13 => Chord::new(&key, &ChordDefinition::new_nine()),
-            14 => Chord::new(&key, &ChordDefinition::new_minor_nine()),
-            15 => Chord::new(&key, &ChordDefinition::new_add_nine()),
-            16 => Chord::new(&key, &ChordDefinition::new_seven_suspended_four()),
-            17 => Chord::new(&key, &ChordDefinition::new_dimished_seven()),
-            18 => Chord::new(&key, &ChordDefinition::new_half_diminished()),
-            19 => Chord::new(&key, &ChordDefinition::new_plus_seven()),
-            20 => Chord::new(&key, &ChordDefinition::new_minor_eleven()),
-            21 => Chord::new(&key, &ChordDefinition::new_augmented_major_seven()),
-            22 => Chord::new(&key, &ChordDefinition::new_dominant_seven_flat_nine()),
-            23 => Chord::new(&key, &ChordDefinition::new_altered_dominant_seven()),
-            _ => panic!("Unexpected"),
+    fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let split = input
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(input.len());
+        let (name, octave) = input.split_at(split);
+        match (NoteName::parse_semitones(name), octave.parse::<i8>()) {
+            (Some(semitones), Ok(octave @ 0..=9)) => Some(NotePitch::from_semitones(
+                octave as i16 * 12 + semitones as i16,
+            )),
+            _ => None,
         }
     }
-}
-#[derive(Clone)]
-struct AudioEngine {
-    device: Arc<Device>,
-    config: StreamConfig,
-}
 
-impl AudioEngine {
-    fn new() -> Self {
-        let host = cpal::default_host();
-        let device = Arc::new(
-            host.default_output_device()
-                .expect("No output device available"),
-        );
-        let config = StreamConfig {
-            channels: 2,
-            sample_rate: SampleRate(44100),
-            buffer_size: BufferSize::Default,
-        };
-        AudioEngine { device, config }
+    fn from_string(input: String) -> Self {
+        match NotePitch::parse(&input) {
+            Some(note_pitch) => note_pitch,
+            None => {
+                println!("Enter a pitch (e.g., E2, F#4):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                NotePitch::from_string(reinput.trim().to_string())
+            }
+        }
     }
 
-    async fn play_audio(&self, frequencies: Vec<f32>, duration_secs: f32) {
-        let device = self.device.clone();
-        let config = self.config.clone();
+    fn from_number(note_number: i8, octave: i8) -> Self {
+        NotePitch {
+            note_name: NoteName::from_number(note_number),
+            octave,
+        }
+    }
 
-        tokio::task::spawn_blocking(move || {
-            let sample_rate = config.sample_rate.0 as f32;
-            let channels = config.channels as usize;
-            let mut phase = 0.0;
-            let phase_increment: Vec<f32> = frequencies
-                .iter()
-                .map(|&freq| (2.0 * PI * freq) / sample_rate)
-                .collect();
+    fn to_number(self: &Self) -> (i8, i8) {
+        (self.note_name.to_number(), self.octave)
+    }
 
-            let stream = device
-                .build_output_stream(
-                    &config,
-                    move |data: &mut [f32], _: &OutputCallbackInfo| {
-                        for sample in data.iter_mut() {
-                            let mut value = 0.0;
-                            for &inc in &phase_increment {
-                                value += (phase + inc).sin();
-                            }
-                            *sample = value / frequencies.len() as f32;
-                            phase += phase_increment[0];
-                            if phase > 2.0 * PI {
-                                phase -= 2.0 * PI;
-                            }
-                        }
-                    },
-                    move |err| {
-                        eprintln!("An error occurred on the audio stream: {:?}", err);
-                    },
-                    None,
-                )
-                .expect("Failed to build output stream");
+    fn from_semitones(semitones: i16) -> Self {
+        NotePitch::from_number(
+            semitones.rem_euclid(12) as i8,
+            semitones.div_euclid(12) as i8,
+        )
+    }
 
-            stream.play().expect("Failed to play the stream");
+    // Each note at the lowest pitch above the one before, starting from the first in octave, so
+    // a scale with its octave repeated ends an octave up.
+    fn stack(notes: &[NoteName], octave: i8) -> Vec<NotePitch> {
+        let mut pitches: Vec<NotePitch> = Vec::new();
+        for note in notes {
+            let mut pitch = NotePitch::from_note_name(note, octave);
+            if let Some(previous) = pitches.last() {
+                let above = (pitch.to_semitones() - previous.to_semitones() - 1).rem_euclid(12) + 1;
+                pitch = NotePitch::from_semitones(previous.to_semitones() + above);
+            }
+            pitches.push(pitch);
+        }
+        pitches
+    }
 
-            std::thread::sleep(Duration::from_secs_f32(duration_secs));
-        })
-        .await
-        .expect("Audio task failed");
+    // Equal tempered, with A4 at 440 Hz.
+    fn frequency(&self) -> f64 {
+        let a4 = NotePitch::new(&NaturalNote::A, &None, 4);
+        440.0 * 2f64.powf((self.to_semitones() - a4.to_semitones()) as f64 / 12.0)
     }
-}
 
-struct Attempt<T, U> {
-    correct_answer: U,
-    attempt_answer: T,
-    correct: bool,
-    attempt_number: u64,
-    time_since_last_attempt: Option<u64>,
-    elapsed_time: u64,
-    attempt_time: NaiveDateTime,
-    game_number: u64,
-}
+    // Semitones above C0, so pitches in different octaves can be compared and subtracted.
+    fn to_semitones(&self) -> i16 {
+        self.octave as i16 * 12 + self.note_name.to_number() as i16
+    }
 
-impl<T: Ord, U: Ord> Attempt<T, U> {
-    fn new(
-        correct_answer: U,
-        attempt_answer: T,
-        attempt_number: u64,
-        time_since_last_attempt: Option<Duration>,
-        elapsed_time: Duration,
-        game_number: u64,
-    ) -> Self {
-        //let correct = correct_answer == attempt_answer;
-        let attempt_time = Utc::now().naive_utc();
-        match time_since_last_attempt {
-            Some(time_since) => Attempt {
-                correct_answer,
-                attempt_answer,
-                correct: false,
-                attempt_number,
-                time_since_last_attempt: Some(time_since.as_secs()),
-                elapsed_time: elapsed_time.as_secs(),
-                attempt_time,
-                game_number,
-            },
-            None => Attempt {
-                correct_answer,
-                attempt_answer,
-                correct: false,
-                attempt_number,
-                time_since_last_attempt: None,
-                elapsed_time: elapsed_time.as_secs(),
-                attempt_time,
-                game_number,
-            },
-        }
+    fn find_note(open_note: &NotePitch, distance: i8) -> Self {
+        let (x, y) = match distance {
+            i if i > 0 => NotePitch::add(&open_note, distance),
+            i if i < 0 => NotePitch::minus(&open_note, distance),
+            0 => return open_note.clone(),
+            _ => panic!("unexpected distance"),
+        };
+        NotePitch::from_number(x, y)
     }
 
-    fn deserialize_time(&self) -> (Option<Duration>, Duration, NaiveDateTime) {
-        match self.time_since_last_attempt {
-            Some(time_since) => (
-                Some(Duration::from_secs(time_since)),
-                Duration::from_secs(self.elapsed_time),
-                self.attempt_time,
-            ),
-            None => (
-                None,
-                Duration::from_secs(self.elapsed_time),
-                self.attempt_time,
-            ),
-        }
+    fn up_step(start_note: &NotePitch, step: &Step) -> Self {
+        let to_add = step.to_number();
+        let (number, octave) = NotePitch::add(start_note, to_add);
+        NotePitch::from_number(number, octave)
     }
-}
 
-struct Game<T, U> {
-    game_number: u64,
-    possible_notes: Vec<U>,
-    attempted_notes: Vec<T>,
-    attempts: Vec<Attempt<T, U>>,
-    start_time: NaiveDateTime,
-    end_time: Option<NaiveDateTime>,
-}
+    fn down_step(start_note: &NotePitch, step: &Step) -> Self {
+        let to_subtract = step.to_number();
+        let (number, octave) = NotePitch::minus(start_note, to_subtract);
+        NotePitch::from_number(number, octave)
+    }
 
-impl<T, U> Game<T, U> {
-    fn new(possible_notes: Vec<U>, game_number: u64) -> Self {
-        Game {
-            game_number,
-            possible_notes,
-            attempted_notes: Vec::new(),
-            attempts: Vec::new(),
-            start_time: Utc::now().naive_utc(),
-            end_time: None,
+    fn add(start_note: &NotePitch, to_add: i8) -> (i8, i8) {
+        let (note_number, octave) = start_note.to_number();
+        let mut octave = octave;
+        let mut number = note_number + to_add;
+        while number >= 12 {
+            number = number - 12;
+            octave = octave + 1;
+        }
+        (number, octave)
+    }
+
+    fn minus(start_note: &NotePitch, to_subtract: i8) -> (i8, i8) {
+        let (note_number, octave) = start_note.to_number();
+        let mut octave = octave;
+        let mut number: i8 = note_number - to_subtract;
+        while number < 0 {
+            number = number + 12;
+            octave = octave - 1;
         }
+        (number, octave)
     }
 }
 
-struct EarTraining<T, U> {
-    audio_engine: AudioEngine,
-    game: Game<T, U>,
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+struct NoteName {
+    natural_note: NaturalNote,
+    accidental: Option<Accidental>,
 }
 
-impl<T, U> EarTraining<T, U> {
-    fn new_notepitch_notepitch(
-        audio_engine: &AudioEngine,
-        possible_notes: &Vec<NotePitch>,
-    ) -> EarTraining<NotePitch, NotePitch> {
-        EarTraining {
-            audio_engine: audio_engine.clone(),
-            game: Game::new(possible_notes.clone(), 1),
-        }
+impl Display for NoteName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        write!(f, "{}", theme.paint(self.get_name(), theme.note_rgb(self)))?;
+        Ok(())
     }
-    fn new_notename_notepitch(
-        audio_engine: &AudioEngine,
-        possible_notes: &Vec<NotePitch>,
-    ) -> EarTraining<NoteName, NotePitch> {
-        EarTraining {
-            audio_engine: audio_engine.clone(),
-            game: Game::new(possible_notes.clone(), 1),
-        }
+}
+
+impl Ord for NoteName {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_number().cmp(&other.to_number())
     }
 }
 
-struct DisplayGroup {
-    instrument: Instrument,
-    key: NoteName,
-    notes: Vec<NoteName>,
-    chord: Option<Chord>,
-    scale: Option<Scale>,
+impl PartialOrd for NoteName {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
-impl DisplayGroup {
-    fn new() -> Self {
-        let instrument = Instrument::new(
-            &InstrumentType::Guitar,
-            &TuningType::Standard,
-            &NotePitch::new(&NaturalNote::C, &None, 2),
-            6,
-            24,
-        );
-        let key = NoteName::new(&NaturalNote::C, &None);
-        let notes: Vec<NoteName> = Vec::new();
-        DisplayGroup {
-            instrument,
-            key,
-            notes,
-            chord: None,
-            scale: None,
+impl From<NoteName> for String {
+    fn from(note_name: NoteName) -> String {
+        Theme::to_ascii(&note_name.get_name())
+    }
+}
+
+impl TryFrom<String> for NoteName {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        NoteName::parse(&value).ok_or(format!("{} is not a note name", value))
+    }
+}
+
+impl NoteName {
+    fn new(natural_note: &NaturalNote, accidental: &Option<Accidental>) -> Self {
+        NoteName {
+            natural_note: natural_note.clone(),
+            accidental: accidental.clone(),
+        }
+    }
+
+    fn get_name(self: &Self) -> String {
+        let natural_note = match self.natural_note {
+            NaturalNote::A => "A".to_string(),
+            NaturalNote::B => "B".to_string(),
+            NaturalNote::C => "C".to_string(),
+            NaturalNote::D => "D".to_string(),
+            NaturalNote::E => "E".to_string(),
+            NaturalNote::F => "F".to_string(),
+            NaturalNote::G => "G".to_string(),
+        };
+        let accidental = match self.accidental {
+            Some(Accidental::Sharp) => "♯".to_string(),
+            Some(Accidental::Flat) => "♭".to_string(),
+            None => "".to_string(),
+        };
+        let name = natural_note + &accidental;
+        name
+    }
+
+    fn from_number(note_number: i8) -> NoteName {
+        match note_number {
+            0 => NoteName::new(&NaturalNote::C, &None),
+            1 => NoteName::new(&NaturalNote::C, &Some(Accidental::Sharp)),
+            2 => NoteName::new(&NaturalNote::D, &None),
+            3 => NoteName::new(&NaturalNote::D, &Some(Accidental::Sharp)),
+            4 => NoteName::new(&NaturalNote::E, &None),
+            5 => NoteName::new(&NaturalNote::F, &None),
+            6 => NoteName::new(&NaturalNote::F, &Some(Accidental::Sharp)),
+            7 => NoteName::new(&NaturalNote::G, &None),
+            8 => NoteName::new(&NaturalNote::G, &Some(Accidental::Sharp)),
+            9 => NoteName::new(&NaturalNote::A, &None),
+            10 => NoteName::new(&NaturalNote::A, &Some(Accidental::Sharp)),
+            11 => NoteName::new(&NaturalNote::B, &None),
+            _ => panic!("unexpected note number"),
+        }
+    }
+
+    fn to_number(self: &Self) -> i8 {
+        let mut number: i8 = match self.natural_note {
+            NaturalNote::C => {
+                0 + match self.accidental {
+                    Some(Accidental::Flat) => panic!("unexpected accidental"),
+                    Some(Accidental::Sharp) => 1,
+                    None => 0,
+                }
+            }
+            NaturalNote::D => {
+                2 + match self.accidental {
+                    Some(Accidental::Flat) => -1,
+                    Some(Accidental::Sharp) => 1,
+                    None => 0,
+                }
+            }
+            NaturalNote::E => {
+                4 + match self.accidental {
+                    Some(Accidental::Flat) => -1,
+                    Some(Accidental::Sharp) => panic!("unexpected accidental"),
+                    None => 0,
+                }
+            }
+            NaturalNote::F => {
+                5 + match self.accidental {
+                    Some(Accidental::Flat) => panic!("unexpected accidental"),
+                    Some(Accidental::Sharp) => 1,
+                    None => 0,
+                }
+            }
+            NaturalNote::G => {
+                7 + match self.accidental {
+                    Some(Accidental::Flat) => -1,
+                    Some(Accidental::Sharp) => 1,
+                    None => 0,
+                }
+            }
+            NaturalNote::A => {
+                9 + match self.accidental {
+                    Some(Accidental::Flat) => -1,
+                    Some(Accidental::Sharp) => 1,
+                    None => 0,
+                }
+            }
+            NaturalNote::B => {
+                11 + match self.accidental {
+                    Some(Accidental::Flat) => -1,
+                    Some(Accidental::Sharp) => panic!("unexpected accidental"),
+                    None => 0,
+                }
+            }
+        };
+        if number > 11 {
+            number = number - 11;
+        }
+        number
+    }
+
+    fn up_step(start_note: &NoteName, step: &Step) -> NoteName {
+        let to_add = step.to_number();
+        let number = NoteName::add(start_note, to_add);
+        NoteName::from_number(number)
+    }
+
+    fn down_step(start_note: &NoteName, step: &Step) -> NoteName {
+        let to_subtract = step.to_number();
+        let number = NoteName::minus(start_note, to_subtract);
+        NoteName::from_number(number)
+    }
+
+    fn add(start_note: &NoteName, to_add: i8) -> i8 {
+        let note_number = start_note.to_number();
+        let mut number = note_number + to_add;
+        while number >= 12 {
+            number = number - 12;
+        }
+        number
+    }
+
+    fn minus(start_note: &NoteName, to_subtract: i8) -> i8 {
+        let note_number = start_note.to_number();
+        let mut number: i8 = note_number - to_subtract;
+        while number < 0 {
+            number = number + 12;
+        }
+        number
+    }
+
+    // "" when the notes hold a major third above the root, "m" for a minor third, and None
+    // when they hold both or neither.
+    fn third_suffix(root: &NoteName, notes: &[NoteName]) -> Option<&'static str> {
+        let distances: Vec<i8> = notes
+            .iter()
+            .map(|note| NoteName::minus(note, root.to_number()))
+            .collect();
+        match (distances.contains(&4), distances.contains(&3)) {
+            (true, false) => Some(""),
+            (false, true) => Some("m"),
+            _ => None,
+        }
+    }
+
+    fn to_rgb(self: &Self) -> Rgb {
+        match self.natural_note {
+            NaturalNote::C => match self.accidental {
+                Some(Accidental::Flat) => panic!("unexpected accidental"),
+                Some(Accidental::Sharp) => Rgb {
+                    0: 191,
+                    1: 64,
+                    2: 191,
+                },
+                None => Rgb { 0: 191, 1: 0, 2: 0 },
+            },
+            NaturalNote::D => match self.accidental {
+                Some(Accidental::Flat) => Rgb {
+                    0: 191,
+                    1: 64,
+                    2: 191,
+                },
+                Some(Accidental::Sharp) => Rgb {
+                    0: 198,
+                    1: 255,
+                    2: 0,
+                },
+                None => Rgb {
+                    0: 255,
+                    1: 191,
+                    2: 64,
+                },
+            },
+            NaturalNote::E => match self.accidental {
+                Some(Accidental::Flat) => Rgb {
+                    0: 198,
+                    1: 255,
+                    2: 0,
+                },
+                Some(Accidental::Sharp) => panic!("unexpected accidental"),
+                None => Rgb {
+                    0: 244,
+                    1: 67,
+                    2: 54,
+                },
+            },
+            NaturalNote::F => match self.accidental {
+                Some(Accidental::Flat) => panic!("unexpected accidental"),
+                Some(Accidental::Sharp) => Rgb {
+                    0: 255,
+                    1: 0,
+                    2: 191,
+                },
+                None => Rgb {
+                    0: 255,
+                    1: 0,
+                    2: 255,
+                },
+            },
+            NaturalNote::G => match self.accidental {
+                Some(Accidental::Flat) => Rgb {
+                    0: 255,
+                    1: 0,
+                    2: 191,
+                },
+                Some(Accidental::Sharp) => Rgb { 0: 0, 1: 191, 2: 0 },
+                None => Rgb { 0: 0, 1: 255, 2: 0 },
+            },
+            NaturalNote::A => match self.accidental {
+                Some(Accidental::Flat) => Rgb { 0: 0, 1: 191, 2: 0 },
+                Some(Accidental::Sharp) => Rgb {
+                    0: 64,
+                    1: 191,
+                    2: 191,
+                },
+                None => Rgb {
+                    0: 165,
+                    1: 255,
+                    2: 235,
+                },
+            },
+            NaturalNote::B => match self.accidental {
+                Some(Accidental::Flat) => Rgb {
+                    0: 64,
+                    1: 191,
+                    2: 191,
+                },
+                Some(Accidental::Sharp) => panic!("unexpected accidental"),
+                None => Rgb {
+                    0: 64,
+                    1: 31,
+                    2: 255,
+                },
+            },
+        }
+    }
+
+    // Accepts sharps and flats as symbols or ASCII, e.g. C#, Db or E♭, without prompting.
+    fn parse(input: &str) -> Option<NoteName> {
+        NoteName::parse_semitones(input)
+            .map(|semitones| NoteName::from_number(semitones.rem_euclid(12)))
+    }
+
+    // Semitones above the C of the same octave letter, from -1 for Cb to 12 for B#, so flats
+    // and enharmonics come out spelt the way the fretboard spells them.
+    fn parse_semitones(input: &str) -> Option<i8> {
+        let mut chars = input.trim().chars();
+        let natural: i8 = match chars.next()?.to_ascii_uppercase() {
+            'C' => 0,
+            'D' => 2,
+            'E' => 4,
+            'F' => 5,
+            'G' => 7,
+            'A' => 9,
+            'B' => 11,
+            _ => return None,
+        };
+        let offset: i8 = match chars.as_str() {
+            "" => 0,
+            "#" | "\u{266F}" => 1,
+            "b" | "\u{266D}" => -1,
+            _ => return None,
+        };
+        Some(natural + offset)
+    }
+
+    fn from_string(input: String) -> NoteName {
+        let input: String = input.to_uppercase();
+        match input.as_str() {
+            "A" => NoteName::new(&NaturalNote::A, &None),
+            "A#" => NoteName::new(&NaturalNote::A, &Some(Accidental::Sharp)),
+            "B" => NoteName::new(&NaturalNote::B, &None),
+            "C" => NoteName::new(&NaturalNote::C, &None),
+            "C#" => NoteName::new(&NaturalNote::C, &Some(Accidental::Sharp)),
+            "D" => NoteName::new(&NaturalNote::D, &None),
+            "D#" => NoteName::new(&NaturalNote::D, &Some(Accidental::Sharp)),
+            "E" => NoteName::new(&NaturalNote::E, &None),
+            "F" => NoteName::new(&NaturalNote::F, &None),
+            "F#" => NoteName::new(&NaturalNote::F, &Some(Accidental::Sharp)),
+            "G" => NoteName::new(&NaturalNote::G, &None),
+            "G#" => NoteName::new(&NaturalNote::G, &Some(Accidental::Sharp)),
+            _ => {
+                println!("Enter a new key (e.g., C, D#, F#):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                let key = NoteName::from_string(reinput.trim().to_string());
+                return key;
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum NaturalNote {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+}
+
+impl Ord for NaturalNote {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_number().cmp(&other.to_number())
+    }
+}
+
+impl PartialOrd for NaturalNote {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl NaturalNote {
+    fn to_number(&self) -> i8 {
+        match self {
+            NaturalNote::A => 9,
+            NaturalNote::B => 11,
+            NaturalNote::C => 0,
+            NaturalNote::D => 2,
+            NaturalNote::E => 4,
+            NaturalNote::F => 5,
+            NaturalNote::G => 7,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+enum TuningType {
+    Open,
+    DropTuning,
+    Standard,
+    Custom,
+}
+
+impl Display for TuningType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            TuningType::Open => write!(f, "Open")?,
+            TuningType::DropTuning => write!(f, "Drop")?,
+            TuningType::Standard => write!(f, "Standard")?,
+            TuningType::Custom => write!(f, "Custom")?,
+        }
+        Ok(())
+    }
+}
+
+impl TuningType {
+    fn from_string(input: String) -> Self {
+        let input_uppercase = input.to_uppercase();
+        /*let mut count = 0;
+        let mut indices = Vec::new();
+        for (index, ch) in input.char_indices() {
+            if ch == ' ' {
+                count += 1;
+                indices.push(index);
+            }
+        }
+        if count > 0 {
+
+        }*/
+        match input_uppercase.as_str() {
+            "OPEN" => TuningType::Open,
+            "DROP" => TuningType::DropTuning,
+            "STANDARD" => TuningType::Standard,
+            "CUSTOM" => TuningType::Custom,
+            _ => {
+                println!("Enter a tuning (e.g., Standard, Drop D, Open G):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                let tuning = TuningType::from_string(reinput.trim().to_string());
+                return tuning;
+            }
+        }
+    }
+}
+
+// Scale lengths in millimetres. A multiscale (fanned fret) neck runs from the bass string's
+// length to the treble string's, with one fret left square to the strings.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+enum ScaleLength {
+    Single(f64),
+    Multiscale {
+        bass: f64,
+        treble: f64,
+        perpendicular_fret: usize,
+    },
+}
+
+impl Display for ScaleLength {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ScaleLength::Single(length) => write!(f, "{:.1} mm", length)?,
+            ScaleLength::Multiscale {
+                bass,
+                treble,
+                perpendicular_fret,
+            } => write!(
+                f,
+                "{:.1}-{:.1} mm, fret {} perpendicular",
+                bass, treble, perpendicular_fret
+            )?,
+        }
+        Ok(())
+    }
+}
+
+impl ScaleLength {
+    // Multiscale lengths step evenly from the lowest string to the highest.
+    fn string_length(&self, string: usize, string_count: usize) -> f64 {
+        match self {
+            ScaleLength::Single(length) => *length,
+            ScaleLength::Multiscale { bass, treble, .. } => match string_count {
+                0 | 1 => *bass,
+                count => bass + (treble - bass) * string as f64 / (count - 1) as f64,
+            },
+        }
+    }
+
+    fn new(instrument_type: &InstrumentType) -> Self {
+        match instrument_type {
+            InstrumentType::Guitar => ScaleLength::Single(648.0),
+            InstrumentType::Bass => ScaleLength::Single(864.0),
+            InstrumentType::Mandolin => ScaleLength::Single(349.0),
+            InstrumentType::Banjo => ScaleLength::Single(667.0),
+            InstrumentType::Ukelelle => ScaleLength::Single(381.0),
+        }
+    }
+
+    // Accepts millimetres or inches, one length or bass-treble for multiscale with an optional
+    // perpendicular fret, e.g. 648, 25.5in, 686-648 or 27-25.5in@8. Empty keeps the current one.
+    fn from_string(input: String) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        if input.is_empty() {
+            return None;
+        }
+        let (lengths, perpendicular_fret) = match input.split_once('@') {
+            Some((lengths, fret)) => (lengths.trim(), fret.trim().parse::<usize>().ok()),
+            None => (input.as_str(), Some(7)),
+        };
+        let (lengths, unit) = match lengths.strip_suffix("in") {
+            Some(lengths) => (lengths, 25.4),
+            None => (lengths.strip_suffix("mm").unwrap_or(lengths), 1.0),
+        };
+        let parsed: Vec<Option<f64>> = lengths
+            .split('-')
+            .map(|length| length.trim().parse::<f64>().ok().filter(|&l| l > 0.0))
+            .collect();
+        match (parsed.as_slice(), perpendicular_fret) {
+            ([Some(length)], _) => Some(ScaleLength::Single(length * unit)),
+            ([Some(bass), Some(treble)], Some(perpendicular_fret)) => {
+                Some(ScaleLength::Multiscale {
+                    bass: bass * unit,
+                    treble: treble * unit,
+                    perpendicular_fret,
+                })
+            }
+            _ => {
+                println!("Enter a scale length (e.g., 648, 25.5in, 686-648, 27-25.5in@8):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                ScaleLength::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+}
+
+// 5-limit just and Pythagorean ratios above the open string, and quarter-comma meantone in cents.
+const JUST_RATIOS: [f64; 12] = [
+    1.0,
+    16.0 / 15.0,
+    9.0 / 8.0,
+    6.0 / 5.0,
+    5.0 / 4.0,
+    4.0 / 3.0,
+    45.0 / 32.0,
+    3.0 / 2.0,
+    8.0 / 5.0,
+    5.0 / 3.0,
+    9.0 / 5.0,
+    15.0 / 8.0,
+];
+const PYTHAGOREAN_RATIOS: [f64; 12] = [
+    1.0,
+    256.0 / 243.0,
+    9.0 / 8.0,
+    32.0 / 27.0,
+    81.0 / 64.0,
+    4.0 / 3.0,
+    729.0 / 512.0,
+    3.0 / 2.0,
+    128.0 / 81.0,
+    27.0 / 16.0,
+    16.0 / 9.0,
+    243.0 / 128.0,
+];
+const MEANTONE_CENTS: [f64; 12] = [
+    0.0, 76.0, 193.2, 310.3, 386.3, 503.4, 579.5, 696.6, 772.6, 889.7, 1006.8, 1082.9,
+];
+
+#[derive(PartialEq, Clone, Debug)]
+enum Temperament {
+    Equal,
+    Just,
+    Pythagorean,
+    Meantone,
+}
+
+impl Display for Temperament {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Temperament::Equal => write!(f, "Equal")?,
+            Temperament::Just => write!(f, "Just")?,
+            Temperament::Pythagorean => write!(f, "Pythagorean")?,
+            Temperament::Meantone => write!(f, "Meantone")?,
+        }
+        Ok(())
+    }
+}
+
+impl Temperament {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "EQUAL" => Temperament::Equal,
+            "JUST" => Temperament::Just,
+            "PYTHAGOREAN" => Temperament::Pythagorean,
+            "MEANTONE" => Temperament::Meantone,
+            _ => {
+                println!("Enter a temperament (Equal, Just, Pythagorean, Meantone):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                Temperament::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+
+    // Frequency ratio of a fret to its open string. Unequal temperaments are taken from each
+    // open string, so every fret is in tune with the string it sits on.
+    fn ratio(&self, fret: usize) -> f64 {
+        let octaves = 2f64.powi((fret / 12) as i32);
+        let step = fret % 12;
+        octaves
+            * match self {
+                Temperament::Equal => 2f64.powf(step as f64 / 12.0),
+                Temperament::Just => JUST_RATIOS[step],
+                Temperament::Pythagorean => PYTHAGOREAN_RATIOS[step],
+                Temperament::Meantone => 2f64.powf(MEANTONE_CENTS[step] / 1200.0),
+            }
+    }
+}
+
+// Approximate unit weights in lb/in for plain steel (PL), nickel wound (NW) and long scale
+// nickel wound bass (XB) strings, gauges in thousandths of an inch.
+const STRING_GAUGES: [StringGauge; 42] = [
+    StringGauge::new("PL008", 0.00001418),
+    StringGauge::new("PL009", 0.00001794),
+    StringGauge::new("PL010", 0.00002215),
+    StringGauge::new("PL011", 0.00002680),
+    StringGauge::new("PL012", 0.00003190),
+    StringGauge::new("PL013", 0.00003744),
+    StringGauge::new("PL014", 0.00004342),
+    StringGauge::new("PL015", 0.00004984),
+    StringGauge::new("PL016", 0.00005671),
+    StringGauge::new("PL017", 0.00006402),
+    StringGauge::new("PL018", 0.00007177),
+    StringGauge::new("PL019", 0.00007997),
+    StringGauge::new("PL020", 0.00008861),
+    StringGauge::new("NW020", 0.00007812),
+    StringGauge::new("NW022", 0.00009380),
+    StringGauge::new("NW024", 0.00011015),
+    StringGauge::new("NW026", 0.00012892),
+    StringGauge::new("NW028", 0.00014683),
+    StringGauge::new("NW030", 0.00016741),
+    StringGauge::new("NW032", 0.00018723),
+    StringGauge::new("NW034", 0.00021022),
+    StringGauge::new("NW036", 0.00023482),
+    StringGauge::new("NW038", 0.00025926),
+    StringGauge::new("NW040", 0.00028713),
+    StringGauge::new("NW042", 0.00032025),
+    StringGauge::new("NW044", 0.00034555),
+    StringGauge::new("NW046", 0.00037636),
+    StringGauge::new("NW049", 0.00042367),
+    StringGauge::new("NW052", 0.00047523),
+    StringGauge::new("NW056", 0.00055410),
+    StringGauge::new("NW060", 0.00064062),
+    StringGauge::new("NW064", 0.00073254),
+    StringGauge::new("NW068", 0.00082188),
+    StringGauge::new("NW072", 0.00092029),
+    StringGauge::new("XB045", 0.00041060),
+    StringGauge::new("XB055", 0.00060784),
+    StringGauge::new("XB065", 0.00083260),
+    StringGauge::new("XB075", 0.00110360),
+    StringGauge::new("XB085", 0.00143020),
+    StringGauge::new("XB095", 0.00176400),
+    StringGauge::new("XB105", 0.00214980),
+    StringGauge::new("XB130", 0.00313600),
+];
+
+// Tension in pounds from unit weight (lb/in), vibrating length (in) and frequency (Hz), with
+// 386.4 in/s² for gravity.
+const GRAVITY_INCHES: f64 = 386.4;
+const MM_PER_INCH: f64 = 25.4;
+const KG_PER_LB: f64 = 0.4536;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+struct StringGauge {
+    name: &'static str,
+    unit_weight: f64,
+}
+
+// Gauges are saved by name and looked up in STRING_GAUGES when loaded.
+impl Serialize for StringGauge {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name)
+    }
+}
+
+impl<'de> Deserialize<'de> for StringGauge {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        StringGauge::find(&name).ok_or(serde::de::Error::custom(format!(
+            "{} is not a known string gauge",
+            name
+        )))
+    }
+}
+
+impl Display for StringGauge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl StringGauge {
+    const fn new(name: &'static str, unit_weight: f64) -> Self {
+        StringGauge { name, unit_weight }
+    }
+
+    fn find(name: &str) -> Option<Self> {
+        STRING_GAUGES
+            .iter()
+            .find(|gauge| gauge.name.eq_ignore_ascii_case(name.trim()))
+            .copied()
+    }
+
+    fn from_string(input: String) -> Self {
+        match StringGauge::find(&input) {
+            Some(gauge) => gauge,
+            None => {
+                let names: Vec<&str> = STRING_GAUGES.iter().map(|gauge| gauge.name).collect();
+                println!("Enter a string gauge ({}):", names.join(", "));
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                StringGauge::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+
+    fn tension(&self, pitch: &NotePitch, length_mm: f64) -> f64 {
+        let length = length_mm / MM_PER_INCH;
+        self.unit_weight * (2.0 * length * pitch.frequency()).powi(2) / GRAVITY_INCHES
+    }
+
+    // The gauge from the table whose tension comes closest to target.
+    fn suggest(pitch: &NotePitch, length_mm: f64, target: f64) -> StringGauge {
+        let distance = |gauge: &StringGauge| (gauge.tension(pitch, length_mm) - target).abs();
+        *STRING_GAUGES
+            .iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .expect("String gauge table is empty")
+    }
+
+    // A common set for six string guitar and four string bass, otherwise a gauge per string
+    // for the middle of the instrument's tension range.
+    fn default_set(instrument: &Instrument) -> Vec<StringGauge> {
+        let names: &[&str] = match (&instrument.instrument_type, instrument.string_count) {
+            (InstrumentType::Guitar, 6) => &["NW046", "NW036", "NW026", "PL017", "PL013", "PL010"],
+            (InstrumentType::Bass, 4) => &["XB105", "XB085", "XB065", "XB045"],
+            _ => &[],
+        };
+        match names.is_empty() {
+            false => names
+                .iter()
+                .map(|&name| StringGauge::from_string(name.to_string()))
+                .collect(),
+            true => {
+                let (slack, tight) = instrument.instrument_type.tension_range();
+                instrument
+                    .tuning
+                    .iter()
+                    .enumerate()
+                    .map(|(i, pitch)| {
+                        let length = instrument
+                            .scale_length
+                            .string_length(i, instrument.string_count);
+                        StringGauge::suggest(pitch, length, (slack + tight) / 2.0)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+// Where a string's tension falls against the instrument type's comfortable range.
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum TensionStatus {
+    TooSlack,
+    Ok,
+    TooTight,
+}
+
+impl Display for TensionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            TensionStatus::TooSlack => write!(f, "too slack")?,
+            TensionStatus::Ok => write!(f, "ok")?,
+            TensionStatus::TooTight => write!(f, "too tight")?,
+        }
+        Ok(())
+    }
+}
+
+const SVG_MARGIN: f64 = 10.0;
+const SVG_STRING_SPACING: f64 = 8.0;
+
+// Fret positions for an instrument's strings, in millimetres from the nut and from the bridge.
+struct FretTable {
+    scale_length: ScaleLength,
+    temperament: Temperament,
+    string_count: usize,
+    fret_count: usize,
+}
+
+impl FretTable {
+    fn string_length(&self, string: usize) -> f64 {
+        self.scale_length.string_length(string, self.string_count)
+    }
+
+    fn nut_distance(&self, string: usize, fret: usize) -> f64 {
+        self.string_length(string) - self.bridge_distance(string, fret)
+    }
+
+    fn bridge_distance(&self, string: usize, fret: usize) -> f64 {
+        self.string_length(string) / self.temperament.ratio(fret)
+    }
+
+    // The strings the table lists: one for a single scale, both outer strings for multiscale.
+    fn edge_strings(&self) -> Vec<(&'static str, usize)> {
+        match self.scale_length {
+            ScaleLength::Single(_) => vec![("", 0)],
+            ScaleLength::Multiscale { .. } => {
+                vec![
+                    ("Bass ", 0),
+                    ("Treble ", self.string_count.saturating_sub(1)),
+                ]
+            }
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("string,fret,scale_length_mm,from_nut_mm,from_bridge_mm\n");
+        for i in 0..self.string_count {
+            for j in 0..=self.fret_count {
+                csv.push_str(&format!(
+                    "{},{},{:.3},{:.3},{:.3}\n",
+                    self.string_count - i,
+                    j,
+                    self.string_length(i),
+                    self.nut_distance(i, j),
+                    self.bridge_distance(i, j)
+                ));
+            }
+        }
+        csv
+    }
+
+    // A full size template in millimetres, lowest string at the bottom. Multiscale strings are
+    // shifted so the perpendicular fret lines up across the neck.
+    fn to_svg(&self) -> String {
+        let perpendicular_fret = match self.scale_length {
+            ScaleLength::Single(_) => 0,
+            ScaleLength::Multiscale {
+                perpendicular_fret, ..
+            } => perpendicular_fret,
+        };
+        let anchors: Vec<f64> = (0..self.string_count)
+            .map(|i| self.nut_distance(i, perpendicular_fret))
+            .collect();
+        let widest = anchors.iter().cloned().fold(0.0, f64::max);
+        let x = |i: usize, fret_distance: f64| SVG_MARGIN + widest - anchors[i] + fret_distance;
+        let y = |i: usize| {
+            SVG_MARGIN + (self.string_count.saturating_sub(1) - i) as f64 * SVG_STRING_SPACING
+        };
+        let length = (0..self.string_count)
+            .map(|i| x(i, self.string_length(i)))
+            .fold(0.0, f64::max);
+        let width = length + SVG_MARGIN;
+        let height = y(0) + SVG_MARGIN * 2.0;
+        let top = self.string_count.saturating_sub(1);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.1}mm\" height=\"{h:.1}mm\" viewBox=\"0 0 {w:.3} {h:.3}\">\n",
+            w = width,
+            h = height
+        );
+        for i in 0..self.string_count {
+            svg.push_str(&format!(
+                "  <line x1=\"{:.3}\" y1=\"{:.3}\" x2=\"{:.3}\" y2=\"{:.3}\" stroke=\"grey\" stroke-width=\"0.3\"/>\n",
+                x(i, 0.0),
+                y(i),
+                x(i, self.string_length(i)),
+                y(i)
+            ));
+        }
+        for j in 0..=self.fret_count {
+            let stroke = match j {
+                0 => 1.0,
+                _ => 0.3,
+            };
+            svg.push_str(&format!(
+                "  <line x1=\"{:.3}\" y1=\"{:.3}\" x2=\"{:.3}\" y2=\"{:.3}\" stroke=\"black\" stroke-width=\"{}\"/>\n",
+                x(0, self.nut_distance(0, j)),
+                y(0),
+                x(top, self.nut_distance(top, j)),
+                y(top),
+                stroke
+            ));
+            svg.push_str(&format!(
+                "  <text x=\"{:.3}\" y=\"{:.3}\" font-size=\"3\" text-anchor=\"middle\">{}</text>\n",
+                x(0, self.nut_distance(0, j)),
+                y(0) + SVG_MARGIN / 2.0,
+                j
+            ));
+        }
+        svg.push_str(&format!(
+            "  <line x1=\"{:.3}\" y1=\"{:.3}\" x2=\"{:.3}\" y2=\"{:.3}\" stroke=\"black\" stroke-width=\"1\"/>\n",
+            x(0, self.string_length(0)),
+            y(0),
+            x(top, self.string_length(top)),
+            y(top)
+        ));
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl Display for FretTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        writeln!(
+            f,
+            "{}",
+            theme.text(format!(
+                "Scale length {}, {} temperament",
+                self.scale_length, self.temperament
+            ))
+        )?;
+        let mut header = format!("{:<6}", "Fret");
+        for (side, _) in self.edge_strings() {
+            for column in ["from nut", "from bridge", "spacing"] {
+                header.push_str(&format!("{:>20}", format!("{}{}", side, column)));
+            }
+        }
+        writeln!(f, "{}", theme.text(header))?;
+        for j in 0..=self.fret_count {
+            let mut row = format!("{:<6}", j);
+            for (_, i) in self.edge_strings() {
+                let spacing = match j {
+                    0 => 0.0,
+                    _ => self.nut_distance(i, j) - self.nut_distance(i, j - 1),
+                };
+                row.push_str(&format!(
+                    "{:>20.2}{:>20.2}{:>20.2}",
+                    self.nut_distance(i, j),
+                    self.bridge_distance(i, j),
+                    spacing
+                ));
+            }
+            writeln!(f, "{}", theme.text(row))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+enum InstrumentType {
+    Guitar,
+    Bass,
+    Mandolin,
+    Banjo,
+    Ukelelle,
+}
+
+impl InstrumentType {
+    fn parse(input: &str) -> Option<Self> {
+        match input.to_uppercase().as_str() {
+            "GUITAR" => Some(InstrumentType::Guitar),
+            "BASS" => Some(InstrumentType::Bass),
+            "MANDOLIN" => Some(InstrumentType::Mandolin),
+            "BANJO" => Some(InstrumentType::Banjo),
+            "UKELELLE" | "UKULELE" => Some(InstrumentType::Ukelelle),
+            _ => None,
+        }
+    }
+
+    fn from_string(input: String) -> Self {
+        match InstrumentType::parse(&input) {
+            Some(instrument_type) => instrument_type,
+            None => {
+                println!("Enter an instrument (Guitar, Bass, Mandolin, Banjo, Ukulele):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                InstrumentType::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+
+    // Comfortable tension per string in pounds; outside it a string is too slack or too tight.
+    fn tension_range(&self) -> (f64, f64) {
+        match self {
+            InstrumentType::Guitar => (11.0, 24.0),
+            InstrumentType::Bass => (25.0, 55.0),
+            InstrumentType::Mandolin => (10.0, 26.0),
+            InstrumentType::Banjo => (8.0, 20.0),
+            InstrumentType::Ukelelle => (6.0, 14.0),
+        }
+    }
+}
+
+impl Display for InstrumentType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            InstrumentType::Guitar => write!(f, "Guitar")?,
+            InstrumentType::Bass => write!(f, "Bass")?,
+            InstrumentType::Mandolin => write!(f, "Mandolin")?,
+            InstrumentType::Banjo => write!(f, "Banjo")?,
+            InstrumentType::Ukelelle => write!(f, "Ukelelle")?,
+        }
+        Ok(())
+    }
+}
+
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum Step {
+    Whole,
+    Half,
+    OneAndAHalf,
+}
+
+impl Ord for Step {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_number().cmp(&other.to_number())
+    }
+}
+
+impl PartialOrd for Step {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Step {
+    fn new_whole() -> Self {
+        Step::Whole
+    }
+
+    fn new_half() -> Self {
+        Step::Half
+    }
+
+    fn new_one_and_a_half() -> Self {
+        Step::OneAndAHalf
+    }
+
+    fn to_number(self: &Self) -> i8 {
+        match self {
+            Step::Whole => 2,
+            Step::Half => 1,
+            Step::OneAndAHalf => 3,
+        }
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let display_character = match self {
+            Step::Whole => "W".to_string(),
+            Step::Half => "H".to_string(),
+            Step::OneAndAHalf => "3/2".to_string(),
+        };
+        write!(f, "{}", display_character)?;
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+struct ScaleDefinition {
+    name: String,
+    steps: Vec<Step>,
+}
+
+impl ScaleDefinition {
+    fn new_major() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        ScaleDefinition {
+            name: "Major".to_string(),
+            steps,
+        }
+    }
+
+    fn new_ionian() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        ScaleDefinition {
+            name: "Ionian".to_string(),
+            steps,
+        }
+    }
+
+    fn new_dorian() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Dorian".to_string(),
+            steps,
+        }
+    }
+
+    fn new_phrygian() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Phrygian".to_string(),
+            steps,
+        }
+    }
+
+    fn new_lydian() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        ScaleDefinition {
+            name: "Lydian".to_string(),
+            steps,
+        }
+    }
+
+    fn new_mixolydian() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Mixolydian".to_string(),
+            steps,
+        }
+    }
+
+    fn new_aeolian() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Aeolian".to_string(),
+            steps,
+        }
+    }
+
+    fn new_locrian() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Locrian".to_string(),
+            steps,
+        }
+    }
+
+    fn new_natural_minor() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Natural Minor".to_string(),
+            steps,
+        }
+    }
+
+    fn new_harmonic_minor() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_one_and_a_half());
+        steps.push(Step::new_half());
+        ScaleDefinition {
+            name: "Harmonic Minor".to_string(),
+            steps,
+        }
+    }
+
+    fn new_melodic_minor_ascending() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        ScaleDefinition {
+            name: "Melodic Minor Ascending".to_string(),
+            steps,
+        }
+    }
+
+    fn new_melodic_minor_descending() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Melodic Minor Descending".to_string(),
+            steps,
+        }
+    }
+
+    fn new_chromatic() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        ScaleDefinition {
+            name: "Chromatic".to_string(),
+            steps,
+        }
+    }
+
+    fn new_whole_tone() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Whole Tone".to_string(),
+            steps,
+        }
+    }
+
+    fn new_major_pentatonic() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_one_and_a_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_one_and_a_half());
+        ScaleDefinition {
+            name: "Major Pentatonic".to_string(),
+            steps,
+        }
+    }
+
+    fn new_minor_pentatonic() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_one_and_a_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_one_and_a_half());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Minor Pentatonic".to_string(),
+            steps,
+        }
+    }
+
+    fn new_blues() -> Self {
+        let mut steps: Vec<Step> = Vec::new();
+        steps.push(Step::new_one_and_a_half());
+        steps.push(Step::new_whole());
+        steps.push(Step::new_half());
+        steps.push(Step::new_half());
+        steps.push(Step::new_one_and_a_half());
+        steps.push(Step::new_whole());
+        ScaleDefinition {
+            name: "Blues".to_string(),
+            steps,
+        }
+    }
+}
+
+impl Display for ScaleDefinition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        let scale_str: &str = "scale: ";
+        write!(f, "{} {}", theme.text(&self.name), theme.text(scale_str))?;
+        for step in &self.steps {
+            write!(f, "{} ", theme.text(step))?;
+        }
+        write!(f, "\n")?;
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct Scale {
+    definition: ScaleDefinition,
+    name: String,
+    notes: Vec<NoteName>,
+}
+
+impl Scale {
+    // Every scale, in any key, holding all of notes. The chromatic scale holds anything so it's
+    // left out.
+    fn fitting(notes: &[NoteName]) -> Vec<Scale> {
+        let mut results: Vec<Scale> = Vec::new();
+        for i in 0..=11 {
+            let key = NoteName::from_number(i);
+            for j in 0..=16 {
+                let scale = Scale::from_number(&key, j);
+                if notes.iter().all(|note| scale.notes.contains(note))
+                    && scale.definition.name != "Chromatic"
+                {
+                    results.push(scale);
+                }
+            }
+        }
+        results
+    }
+
+    fn new(root_note: &NoteName, definition: &ScaleDefinition) -> Self {
+        let mut notes: Vec<NoteName> = Vec::new();
+        notes.push(root_note.clone());
+        let mut count: usize = 0;
+        for step in &definition.steps {
+            notes.push(NoteName::up_step(&notes[count], &step));
+            count = count + 1;
+        }
+        Scale {
+            definition: definition.clone(),
+            name: format!("{} {}", &notes[0].get_name(), definition.name),
+            notes,
+        }
+    }
+
+    // The scale's pitches from start, or the first scale note above it, up through octaves
+    // octaves so the run ends on the note it began on.
+    fn run(&self, start: &NotePitch, octaves: usize) -> Vec<NotePitch> {
+        let classes = self.pitch_classes();
+        let in_scale = |semitones: i16| classes.contains(&(semitones.rem_euclid(12) as i8));
+        let first = (start.to_semitones()..start.to_semitones() + 12)
+            .find(|&semitones| in_scale(semitones))
+            .unwrap_or(start.to_semitones());
+        (first..=first + 12 * octaves as i16)
+            .filter(|&semitones| in_scale(semitones))
+            .map(NotePitch::from_semitones)
+            .collect()
+    }
+
+    // Distinct note numbers in scale order, without the repeated octave.
+    fn pitch_classes(&self) -> Vec<i8> {
+        let mut numbers: Vec<i8> = Vec::new();
+        for note in &self.notes {
+            if !numbers.contains(&note.to_number()) {
+                numbers.push(note.to_number());
+            }
+        }
+        numbers
+    }
+
+    // Where the definition comes in Scale::from_number, for stepping through the scales.
+    fn number(&self) -> Option<i8> {
+        (0..=16).find(|&input| {
+            Scale::from_number(&self.notes[0], input).definition.name == self.definition.name
+        })
+    }
+
+    // The reverse of name, e.g. "D Minor Pentatonic".
+    fn from_name(name: &str) -> Option<Self> {
+        let (root, definition) = name.trim().split_once(' ')?;
+        let root = NoteName::parse(root)?;
+        (0..=16)
+            .map(|input| Scale::from_number(&root, input))
+            .find(|scale| {
+                scale
+                    .definition
+                    .name
+                    .eq_ignore_ascii_case(definition.trim())
+            })
+    }
+
+    fn from_number(key: &NoteName, input: i8) -> Self {
+        match input {
+            0 => Scale::new(&key, &ScaleDefinition::new_major()),
+            1 => Scale::new(&key, &ScaleDefinition::new_ionian()),
+            2 => Scale::new(&key, &ScaleDefinition::new_dorian()),
+            3 => Scale::new(&key, &ScaleDefinition::new_phrygian()),
+            4 => Scale::new(&key, &ScaleDefinition::new_lydian()),
+            5 => Scale::new(&key, &ScaleDefinition::new_mixolydian()),
+            6 => Scale::new(&key, &ScaleDefinition::new_aeolian()),
+            7 => Scale::new(&key, &ScaleDefinition::new_locrian()),
+            8 => Scale::new(&key, &ScaleDefinition::new_natural_minor()),
+            9 => Scale::new(&key, &ScaleDefinition::new_harmonic_minor()),
+            10 => Scale::new(&key, &ScaleDefinition::new_melodic_minor_ascending()),
+            11 => Scale::new(&key, &ScaleDefinition::new_melodic_minor_descending()),
+            12 => Scale::new(&key, &ScaleDefinition::new_chromatic()),
+            13 => Scale::new(&key, &ScaleDefinition::new_whole_tone()),
+            14 => Scale::new(&key, &ScaleDefinition::new_major_pentatonic()),
+            15 => Scale::new(&key, &ScaleDefinition::new_minor_pentatonic()),
+            16 => Scale::new(&key, &ScaleDefinition::new_blues()),
+            _ => panic!("unexpected"),
+        }
+    }
+
+    fn from_string(key: &NoteName, input: String) -> Option<Self> {
+        let input: String = input.to_uppercase();
+        match input.as_str() {
+            "MAJOR" => Some(Scale::new(&key, &ScaleDefinition::new_major())),
+            "IONIAN" => Some(Scale::new(&key, &ScaleDefinition::new_ionian())),
+            "DORIAN" => Some(Scale::new(&key, &ScaleDefinition::new_dorian())),
+            "PHRYGIAN" => Some(Scale::new(&key, &ScaleDefinition::new_phrygian())),
+            "LYDIAN" => Some(Scale::new(&key, &ScaleDefinition::new_lydian())),
+            "MIXOLYDIAN" => Some(Scale::new(&key, &ScaleDefinition::new_mixolydian())),
+            "AEOLIAN" => Some(Scale::new(&key, &ScaleDefinition::new_aeolian())),
+            "LOCRIAN" => Some(Scale::new(&key, &ScaleDefinition::new_locrian())),
+            "NATURAL MINOR" => Some(Scale::new(&key, &ScaleDefinition::new_natural_minor())),
+            "HARMONIC MINOR" => Some(Scale::new(&key, &ScaleDefinition::new_harmonic_minor())),
+            "MELODIC MINOR ASCENDING" => {
+                Some(Scale::new(&key, &ScaleDefinition::new_melodic_minor_ascending()))
+            }
+            "MELODIC MINOR DESCENDING" => {
+                Some(Scale::new(&key, &ScaleDefinition::new_melodic_minor_descending()))
+            }
+            "CHROMATIC" => Some(Scale::new(&key, &ScaleDefinition::new_chromatic())),
+            "WHOLE TONE" => Some(Scale::new(&key, &ScaleDefinition::new_whole_tone())),
+            "MAJOR PENTATONIC" => Some(Scale::new(&key, &ScaleDefinition::new_major_pentatonic())),
+            "MINOR PENTATONIC" => Some(Scale::new(&key, &ScaleDefinition::new_minor_pentatonic())),
+            "BLUES" => Some(Scale::new(&key, &ScaleDefinition::new_blues())),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Scale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        let scale_str: &str = "scale: ";
+        write!(f, "{} {}", theme.text(&self.name), theme.text(scale_str))?;
+        for note in &self.notes {
+            write!(f, "{} ", note)?;
+        }
+        write!(f, "\n")?;
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum Accidental {
+    Sharp,
+    Flat,
+}
+
+impl Ord for Accidental {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_number().cmp(&other.to_number())
+    }
+}
+
+impl PartialOrd for Accidental {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Accidental {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Accidental::Sharp => write!(f, "♯")?,
+            Accidental::Flat => write!(f, "♭")?,
+        }
+        Ok(())
+    }
+}
+
+impl Accidental {
+    fn to_number(&self) -> i8 {
+        match self {
+            Accidental::Sharp => 1,
+            Accidental::Flat => -1,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct Interval {
+    accidental: Option<Accidental>,
+    interval: i8,
+}
+
+impl Interval {
+    fn new(interval: i8, accidental: Option<Accidental>) -> Self {
+        Interval {
+            interval,
+            accidental,
+        }
+    }
+
+    // Spells a distance above the root as a scale degree, flattening rather than sharpening.
+    fn from_semitones(semitones: i8) -> Self {
+        match semitones.rem_euclid(12) {
+            0 => Interval::new(1, None),
+            1 => Interval::new(2, Some(Accidental::Flat)),
+            2 => Interval::new(2, None),
+            3 => Interval::new(3, Some(Accidental::Flat)),
+            4 => Interval::new(3, None),
+            5 => Interval::new(4, None),
+            6 => Interval::new(5, Some(Accidental::Flat)),
+            7 => Interval::new(5, None),
+            8 => Interval::new(6, Some(Accidental::Flat)),
+            9 => Interval::new(6, None),
+            10 => Interval::new(7, Some(Accidental::Flat)),
+            _ => Interval::new(7, None),
+        }
+    }
+
+    fn degree_label(&self) -> String {
+        match (self.interval, &self.accidental) {
+            (1, None) => "R".to_string(),
+            _ => self.to_string(),
+        }
+    }
+
+    fn quality_name(semitones: i8) -> &'static str {
+        match semitones.rem_euclid(12) {
+            0 => "P1",
+            1 => "m2",
+            2 => "M2",
+            3 => "m3",
+            4 => "M3",
+            5 => "P4",
+            6 => "TT",
+            7 => "P5",
+            8 => "m6",
+            9 => "M6",
+            10 => "m7",
+            _ => "M7",
+        }
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self.accidental {
+            Some(Accidental::Sharp) => write!(f, "♯{}", self.interval)?,
+            Some(Accidental::Flat) => write!(f, "♭{}", self.interval)?,
+            None => write!(f, "{}", self.interval)?,
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct ChordDefinition {
+    name: String,
+    naming_convention: String,
+    intervals: Vec<Interval>,
+}
+
+impl Display for ChordDefinition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        let chord_str: &str = "chord: ";
+        write!(f, "{} {}", theme.text(&self.name), theme.text(chord_str))?;
+        for interval in &self.intervals {
+            write!(f, "{} ", theme.text(interval))?;
+        }
+        write!(f, "\n")?;
+        Ok(())
+    }
+}
+
+impl ChordDefinition {
+    fn new_major() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, None));
+        ChordDefinition {
+            name: "Major".to_string(),
+            naming_convention: "".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_minor() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, Some(Accidental::Flat)));
+        intervals.push(Interval::new(5, None));
+        ChordDefinition {
+            name: "Minor".to_string(),
+            naming_convention: "m".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_diminished() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, Some(Accidental::Flat)));
+        intervals.push(Interval::new(5, Some(Accidental::Flat)));
+        ChordDefinition {
+            name: "Diminished".to_string(),
+            naming_convention: "dim".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_augmented() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, Some(Accidental::Sharp)));
+        ChordDefinition {
+            name: "Augmented".to_string(),
+            naming_convention: "aug".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_suspended_second() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(2, None));
+        intervals.push(Interval::new(5, None));
+        ChordDefinition {
+            name: "Suspended 2".to_string(),
+            naming_convention: "sus2".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_suspended_four() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(4, None));
+        intervals.push(Interval::new(5, None));
+        ChordDefinition {
+            name: "Suspended 4".to_string(),
+            naming_convention: "sus4".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_power() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(5, None));
+        ChordDefinition {
+            name: "Power".to_string(),
+            naming_convention: "5".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_major_seven() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(7, None));
+        ChordDefinition {
+            name: "Major 7".to_string(),
+            naming_convention: "maj7".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_minor_seven() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, Some(Accidental::Flat)));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        ChordDefinition {
+            name: "Minor 7".to_string(),
+            naming_convention: "m7".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_dominant_seven() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        ChordDefinition {
+            name: "Dominant 7".to_string(),
+            naming_convention: "7".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_minor_major_seven() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, Some(Accidental::Flat)));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(7, None));
+        ChordDefinition {
+            name: "Minor Major 7".to_string(),
+            naming_convention: "m(Maj7)".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_six() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(6, None));
+        ChordDefinition {
+            name: "6".to_string(),
+            naming_convention: "6".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_minor_six() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, Some(Accidental::Flat)));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(6, None));
+        ChordDefinition {
+            name: "Minor 6".to_string(),
+            naming_convention: "m6".to_string(),
+            intervals,
+        }
+    }
+    fn new_nine() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        intervals.push(Interval::new(9, None));
+        ChordDefinition {
+            name: "9".to_string(),
+            naming_convention: "9".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_minor_nine() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, Some(Accidental::Flat)));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        intervals.push(Interval::new(9, None));
+        ChordDefinition {
+            name: "Minor 9".to_string(),
+            naming_convention: "m9".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_add_nine() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(9, None));
+        ChordDefinition {
+            name: "Add 9".to_string(),
+            naming_convention: "add9".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_seven_suspended_four() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(4, None));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        ChordDefinition {
+            name: "7 Suspended 4".to_string(),
+            naming_convention: "7sus4".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_dimished_seven() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, Some(Accidental::Flat)));
+        intervals.push(Interval::new(5, Some(Accidental::Flat)));
+        intervals.push(Interval::new(6, None));
+        ChordDefinition {
+            name: "Diminished 7".to_string(),
+            naming_convention: "dim7".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_half_diminished() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, Some(Accidental::Flat)));
+        intervals.push(Interval::new(5, Some(Accidental::Flat)));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        ChordDefinition {
+            name: "Half Diminished".to_string(),
+            naming_convention: "7♭5".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_plus_seven() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, Some(Accidental::Sharp)));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        ChordDefinition {
+            name: "Plus 7".to_string(),
+            naming_convention: "+7".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_minor_eleven() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, Some(Accidental::Flat)));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        intervals.push(Interval::new(9, None));
+        intervals.push(Interval::new(11, None));
+        ChordDefinition {
+            name: "Minor 11".to_string(),
+            naming_convention: "m11".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_augmented_major_seven() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, Some(Accidental::Sharp)));
+        intervals.push(Interval::new(7, None));
+        ChordDefinition {
+            name: "Augmented Major 7".to_string(),
+            naming_convention: "Maj7♯5".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_dominant_seven_flat_nine() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, None));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        intervals.push(Interval::new(9, Some(Accidental::Flat)));
+        ChordDefinition {
+            name: "Dominant 7 Flat 9".to_string(),
+            naming_convention: "7♭9".to_string(),
+            intervals,
+        }
+    }
+
+    fn new_altered_dominant_seven() -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        intervals.push(Interval::new(1, None));
+        intervals.push(Interval::new(3, None));
+        intervals.push(Interval::new(5, Some(Accidental::Sharp)));
+        intervals.push(Interval::new(7, Some(Accidental::Flat)));
+        intervals.push(Interval::new(9, Some(Accidental::Sharp)));
+        ChordDefinition {
+            name: "Altered Dominant 7".to_string(),
+            naming_convention: "7♯5♯9".to_string(),
+            intervals,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct Chord {
+    definition: ChordDefinition,
+    notes: Vec<NoteName>,
+    name: String,
+    short_name: String,
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let theme = Theme::current();
+        let open_bracket: &str = "(";
+        let closed_bracket: &str = ")";
+        let chord_str: &str = "chord: ";
+        write!(
+            f,
+            "{} {}{}{} {}",
+            theme.text(&self.name),
+            theme.text(open_bracket),
+            theme.text(&self.short_name),
+            theme.text(closed_bracket),
+            theme.text(chord_str)
+        )?;
+        for note in &self.notes {
+            write!(f, "{} ", note)?;
+        }
+        write!(f, "\n")?;
+        Ok(())
+    }
+}
+
+impl Chord {
+    // Every chord, in any key, whose notes are all among notes.
+    fn fitting(notes: &[NoteName]) -> Vec<Chord> {
+        let mut results: Vec<Chord> = Vec::new();
+        for i in 0..=11 {
+            let key = NoteName::from_number(i);
+            for j in 0..=23 {
+                let chord = Chord::from_number(&key, j);
+                if chord.notes.iter().all(|note| notes.contains(note)) {
+                    results.push(chord);
+                }
+            }
+        }
+        results
+    }
+
+    fn new(root_note: &NoteName, definition: &ChordDefinition) -> Self {
+        let scale = Scale::new(root_note, &ScaleDefinition::new_major());
+        let mut notes: Vec<NoteName> = Vec::new();
+        let scale_length = scale.notes.len();
+
+        for interval in &definition.intervals {
+            let interval_value = interval.interval as usize;
+
+            let i: usize = if interval_value > scale_length {
+                interval_value % scale_length
+            } else {
+                interval_value - 1
+            };
+
+            let base_note = &scale.notes[i];
+            let note = match interval.accidental {
+                None => base_note.clone(),
+                Some(Accidental::Flat) => NoteName::down_step(base_note, &Step::new_half()),
+                Some(Accidental::Sharp) => NoteName::up_step(base_note, &Step::new_half()),
+            };
+
+            notes.push(note);
+        }
+
+        Chord {
+            definition: definition.clone(),
+            notes,
+            name: format!("{} {}", NoteName::get_name(root_note), definition.name),
+            short_name: format!(
+                "{}{}",
+                NoteName::get_name(root_note),
+                definition.naming_convention
+            ),
+        }
+    }
+
+    fn from_string(key: &NoteName, input: String) -> Option<Self> {
+        let input_uppercase: String = input.to_uppercase();
+        match input_uppercase.as_str() {
+            "MAJOR" => Some(Chord::new(&key, &ChordDefinition::new_major())),
+            "MINOR" => Some(Chord::new(&key, &ChordDefinition::new_minor())),
+            "DIMINISHED" => Some(Chord::new(&key, &ChordDefinition::new_diminished())),
+            "AUGMENTED" => Some(Chord::new(&key, &ChordDefinition::new_augmented())),
+            "SUSPENDED TWO" => Some(Chord::new(&key, &ChordDefinition::new_suspended_second())),
+            "SUSPENDED FOUR" => Some(Chord::new(&key, &ChordDefinition::new_suspended_four())),
+            "POWER" => Some(Chord::new(&key, &ChordDefinition::new_power())),
+            "MAJOR SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_major_seven())),
+            "MINOR SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_minor_seven())),
+            "DOMINANT SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_dominant_seven())),
+            "MINOR MAJOR SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_minor_major_seven())),
+            "SIX" => Some(Chord::new(&key, &ChordDefinition::new_six())),
+            "MINOR SIX" => Some(Chord::new(&key, &ChordDefinition::new_minor_six())),
+            "NINE" => Some(Chord::new(&key, &ChordDefinition::new_nine())),
+            "MINOR NINE" => Some(Chord::new(&key, &ChordDefinition::new_minor_nine())),
+            "ADD NINE" => Some(Chord::new(&key, &ChordDefinition::new_add_nine())),
+            "SEVEN SUSPENDED FOUR" => Some(Chord::new(&key, &ChordDefinition::new_seven_suspended_four())),
+            "DIMINISHED SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_dimished_seven())),
+            "HALF DIMINISHED" => Some(Chord::new(&key, &ChordDefinition::new_half_diminished())),
+            "PLUS SEVEN" => Some(Chord::new(&key, &ChordDefinition::new_plus_seven())),
+            "MINOR ELEVEN" => Some(Chord::new(&key, &ChordDefinition::new_minor_eleven())),
+            "AUGMENTED MAJOR SEVEN" => {
+                Some(Chord::new(&key, &ChordDefinition::new_augmented_major_seven()))
+            }
+            "DOMINANT SEVEN FLAT NINE" => {
+                Some(Chord::new(&key, &ChordDefinition::new_dominant_seven_flat_nine()))
+            }
+            "ALTERED DOMINANT SEVEN" => {
+                Some(Chord::new(&key, &ChordDefinition::new_altered_dominant_seven()))
+            }
+            _ => None,
+        }
+    }
+
+    // Where the definition comes in Chord::from_number, for stepping through the chords.
+    fn number(&self) -> Option<i8> {
+        (0..=23).find(|&input| {
+            Chord::from_number(&self.notes[0], input).definition.name == self.definition.name
+        })
+    }
+
+    // The reverse of name, e.g. "C# Major 7".
+    fn from_name(name: &str) -> Option<Self> {
+        let (root, definition) = name.trim().split_once(' ')?;
+        let root = NoteName::parse(root)?;
+        (0..=23)
+            .map(|input| Chord::from_number(&root, input))
+            .find(|chord| {
+                chord
+                    .definition
+                    .name
+                    .eq_ignore_ascii_case(definition.trim())
+            })
+    }
+
+    fn from_number(key: &NoteName, input: i8) -> Self {
+        match input {
+            0 => Chord::new(&key, &ChordDefinition::new_major()),
+            1 => Chord::new(&key, &ChordDefinition::new_minor()),
+            2 => Chord::new(&key, &ChordDefinition::new_diminished()),
+            3 => Chord::new(&key, &ChordDefinition::new_augmented()),
+            4 => Chord::new(&key, &ChordDefinition::new_suspended_second()),
+            5 => Chord::new(&key, &ChordDefinition::new_suspended_four()),
+            6 => Chord::new(&key, &ChordDefinition::new_power()),
+            7 => Chord::new(&key, &ChordDefinition::new_major_seven()),
+            8 => Chord::new(&key, &ChordDefinition::new_minor_seven()),
+            9 => Chord::new(&key, &ChordDefinition::new_dominant_seven()),
+            10 => Chord::new(&key, &ChordDefinition::new_minor_major_seven()),
+            11 => Chord::new(&key, &ChordDefinition::new_six()),
+            12 => Chord::new(&key, &ChordDefinition::new_minor_six()),
+            13 => Chord::new(&key, &ChordDefinition::new_nine()),
+            14 => Chord::new(&key, &ChordDefinition::new_minor_nine()),
+            15 => Chord::new(&key, &ChordDefinition::new_add_nine()),
+            16 => Chord::new(&key, &ChordDefinition::new_seven_suspended_four()),
+            17 => Chord::new(&key, &ChordDefinition::new_dimished_seven()),
+            18 => Chord::new(&key, &ChordDefinition::new_half_diminished()),
+            19 => Chord::new(&key, &ChordDefinition::new_plus_seven()),
+            20 => Chord::new(&key, &ChordDefinition::new_minor_eleven()),
+            21 => Chord::new(&key, &ChordDefinition::new_augmented_major_seven()),
+            22 => Chord::new(&key, &ChordDefinition::new_dominant_seven_flat_nine()),
+            23 => Chord::new(&key, &ChordDefinition::new_altered_dominant_seven()),
+            _ => panic!("Unexpected"),
+        }
+    }
+}
+// A saved or custom instrument as written to disk, with the tuning from the lowest string.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+struct InstrumentDefinition {
+    instrument_type: InstrumentType,
+    tuning_type: TuningType,
+    tuning: Vec<NotePitch>,
+    fret_count: usize,
+    scale_length: ScaleLength,
+    #[serde(default)]
+    string_gauges: Vec<StringGauge>,
+}
+
+impl InstrumentDefinition {
+    fn from_instrument(instrument: &Instrument) -> Self {
+        InstrumentDefinition {
+            instrument_type: instrument.instrument_type.clone(),
+            tuning_type: instrument.tuning_type.clone(),
+            tuning: instrument.tuning.clone(),
+            fret_count: instrument.fret_count,
+            scale_length: instrument.scale_length.clone(),
+            string_gauges: instrument.string_gauges.clone(),
+        }
+    }
+
+    // Hand-edited files can leave out the strings or frets an instrument needs.
+    fn to_instrument(&self) -> io::Result<Instrument> {
+        Instrument::check_size(&self.tuning, self.fret_count)?;
+        let mut instrument = Instrument::from_tuning(
+            &self.instrument_type,
+            &self.tuning_type,
+            &self.tuning,
+            self.fret_count,
+        );
+        instrument.scale_length = self.scale_length.clone();
+        if self.string_gauges.len() == instrument.string_count {
+            instrument.string_gauges = self.string_gauges.clone();
+        }
+        Ok(instrument)
+    }
+}
+
+// Everything a Runtime shows, with chords and scales by name, e.g. "C# Major 7".
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+struct Session {
+    key: NoteName,
+    #[serde(default)]
+    notes: Vec<NoteName>,
+    #[serde(default)]
+    chord: Option<String>,
+    #[serde(default)]
+    scale: Option<String>,
+    #[serde(default)]
+    progression: Vec<String>,
+    #[serde(default = "Theme::current")]
+    theme: Theme,
+    #[serde(default)]
+    render_options: RenderOptions,
+    instrument: InstrumentDefinition,
+}
+
+// Sessions and instruments are kept as TOML files under the user's config directory, e.g.
+// ~/.config/fretninja/sessions/practice.toml on Linux.
+#[derive(PartialEq, Clone, Debug)]
+enum ConfigKind {
+    Session,
+    Instrument,
+}
+
+impl Display for ConfigKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ConfigKind::Session => write!(f, "session")?,
+            ConfigKind::Instrument => write!(f, "instrument")?,
+        }
+        Ok(())
+    }
+}
+
+impl ConfigKind {
+    fn directory(&self) -> io::Result<PathBuf> {
+        let config = dirs::config_dir().ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no config directory for this user",
+        ))?;
+        let folder = match self {
+            ConfigKind::Session => "sessions",
+            ConfigKind::Instrument => "instruments",
+        };
+        Ok(config.join("fretninja").join(folder))
+    }
+
+    // Names become file names, so anything that could leave the directory is refused.
+    fn path(&self, name: &str) -> io::Result<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == ' ');
+        match valid {
+            true => Ok(self.directory()?.join(format!("{}.toml", name))),
+            false => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "names may only use letters, numbers, spaces, - and _",
+            )),
+        }
+    }
+
+    fn save<T: Serialize>(&self, name: &str, value: &T) -> io::Result<PathBuf> {
+        let path = self.path(name)?;
+        let contents = toml::to_string_pretty(value)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        std::fs::create_dir_all(self.directory()?)?;
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    fn load<T: DeserializeOwned>(&self, name: &str) -> io::Result<T> {
+        let contents = std::fs::read_to_string(self.path(name)?)?;
+        toml::from_str(&contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    fn list(&self) -> Vec<String> {
+        let Ok(entries) = self.directory().and_then(std::fs::read_dir) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                match path.extension()?.to_str()? {
+                    "toml" => Some(path.file_stem()?.to_str()?.to_string()),
+                    _ => None,
+                }
+            })
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+// The shape of each voice's oscillator.
+#[derive(PartialEq, Clone, Debug)]
+enum Waveform {
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+}
+
+impl Display for Waveform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Waveform::Sine => write!(f, "Sine")?,
+            Waveform::Triangle => write!(f, "Triangle")?,
+            Waveform::Saw => write!(f, "Saw")?,
+            Waveform::Square => write!(f, "Square")?,
+        }
+        Ok(())
+    }
+}
+
+impl Waveform {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "SINE" => Waveform::Sine,
+            "TRIANGLE" => Waveform::Triangle,
+            "SAW" | "SAWTOOTH" => Waveform::Saw,
+            "SQUARE" => Waveform::Square,
+            _ => {
+                println!("Enter a waveform (Sine, Triangle, Saw, Square):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                Waveform::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+
+    // One cycle from phase 0 to 1, between -1 and 1.
+    fn sample(&self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Saw => 2.0 * phase - 1.0,
+            Waveform::Square => match phase < 0.5 {
+                true => 1.0,
+                false => -1.0,
+            },
+        }
+    }
+}
+
+// Attack, decay and release in seconds, sustain as a level from 0 to 1. Ramping in and out
+// keeps notes from clicking when they start and stop.
+#[derive(PartialEq, Clone, Debug)]
+struct Envelope {
+    attack: f32,
+    decay: f32,
+    sustain: f32,
+    release: f32,
+}
+
+impl Display for Envelope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "Attack {}s, decay {}s, sustain {}, release {}s",
+            self.attack, self.decay, self.sustain, self.release
+        )
+    }
+}
+
+impl Envelope {
+    fn new() -> Self {
+        Envelope {
+            attack: 0.01,
+            decay: 0.1,
+            sustain: 0.7,
+            release: 0.2,
+        }
+    }
+
+    fn from_string(input: String) -> Self {
+        let values: Vec<Option<f32>> = input
+            .split(',')
+            .map(|value| value.trim().parse::<f32>().ok().filter(|v| *v >= 0.0))
+            .collect();
+        match values[..] {
+            [Some(attack), Some(decay), Some(sustain @ 0.0..=1.0), Some(release)] => Envelope {
+                attack,
+                decay,
+                sustain,
+                release,
+            },
+            _ => {
+                println!("Enter attack, decay, sustain and release (e.g., 0.01, 0.1, 0.7, 0.2):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                Envelope::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+
+    // The level elapsed seconds into a note that's held for held seconds and then released.
+    fn level(&self, elapsed: f32, held: f32) -> f32 {
+        if elapsed >= held {
+            let released = elapsed - held;
+            return match released < self.release {
+                true => self.level(held, f32::INFINITY) * (1.0 - released / self.release),
+                false => 0.0,
+            };
+        }
+        if elapsed < self.attack {
+            elapsed / self.attack
+        } else if elapsed < self.attack + self.decay {
+            1.0 - (1.0 - self.sustain) * (elapsed - self.attack) / self.decay
+        } else {
+            self.sustain
+        }
+    }
+}
+
+// What makes each voice's sound: an oscillator with the chosen waveform, or a modelled
+// plucked string. Damping shortens the string's ring and brightness keeps more of its high
+// partials, both from 0 to 1.
+#[derive(PartialEq, Clone, Debug)]
+enum VoiceModel {
+    Oscillator,
+    PluckedString { damping: f32, brightness: f32 },
+}
+
+impl Display for VoiceModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            VoiceModel::Oscillator => write!(f, "Oscillator")?,
+            VoiceModel::PluckedString {
+                damping,
+                brightness,
+            } => write!(
+                f,
+                "Plucked string (damping {}, brightness {})",
+                damping, brightness
+            )?,
+        }
+        Ok(())
+    }
+}
+
+impl VoiceModel {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "OSCILLATOR" => VoiceModel::Oscillator,
+            "PLUCKED" | "PLUCKED STRING" | "STRING" => VoiceModel::PluckedString {
+                damping: 0.2,
+                brightness: 0.5,
+            },
+            _ => {
+                println!("Enter a voice (Oscillator, Plucked):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                VoiceModel::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+}
+
+// How the engine's voices sound: their model, waveform, envelope and the overall volume.
+#[derive(PartialEq, Clone, Debug)]
+struct SynthSettings {
+    model: VoiceModel,
+    waveform: Waveform,
+    envelope: Envelope,
+    gain: f32,
+}
+
+impl SynthSettings {
+    fn new() -> Self {
+        SynthSettings {
+            model: VoiceModel::Oscillator,
+            waveform: Waveform::Sine,
+            envelope: Envelope::new(),
+            gain: 0.3,
+        }
+    }
+}
+
+// Gap between neighbouring strings when a chord is strummed.
+const STRUM_SPREAD_SECS: f32 = 0.03;
+
+// The shapes a scale is practised in: straight through, leaping an interval from each note
+// (2 steps for thirds) or in overlapping groups of notes.
+#[derive(PartialEq, Clone, Debug)]
+enum ScaleSequence {
+    Straight,
+    Intervals(usize),
+    Groups(usize),
+}
+
+impl Display for ScaleSequence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ScaleSequence::Straight => write!(f, "Straight")?,
+            ScaleSequence::Intervals(2) => write!(f, "In thirds")?,
+            ScaleSequence::Intervals(3) => write!(f, "In fourths")?,
+            ScaleSequence::Intervals(4) => write!(f, "In fifths")?,
+            ScaleSequence::Intervals(5) => write!(f, "In sixths")?,
+            ScaleSequence::Intervals(steps) => write!(f, "In leaps of {} steps", steps)?,
+            ScaleSequence::Groups(size) => write!(f, "In groups of {}", size)?,
+        }
+        Ok(())
+    }
+}
+
+impl ScaleSequence {
+    fn from_string(input: String) -> Self {
+        let input_uppercase = input.to_uppercase();
+        match input_uppercase.as_str() {
+            "STRAIGHT" | "" => return ScaleSequence::Straight,
+            "THIRDS" => return ScaleSequence::Intervals(2),
+            "FOURTHS" => return ScaleSequence::Intervals(3),
+            "FIFTHS" => return ScaleSequence::Intervals(4),
+            "SIXTHS" => return ScaleSequence::Intervals(5),
+            _ => (),
+        }
+        let size = input_uppercase
+            .strip_prefix("GROUPS OF")
+            .and_then(|size| size.trim().parse::<usize>().ok());
+        match size {
+            Some(size @ 2..=8) => ScaleSequence::Groups(size),
+            _ => {
+                println!(
+                    "Enter a sequence (Straight, Thirds, Fourths, Fifths, Sixths, Groups of 3):"
+                );
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                ScaleSequence::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+
+    fn apply(&self, run: &[NotePitch]) -> Vec<NotePitch> {
+        match self {
+            ScaleSequence::Straight => run.to_vec(),
+            ScaleSequence::Intervals(steps) => (0..run.len().saturating_sub(*steps))
+                .flat_map(|i| [run[i].clone(), run[i + steps].clone()])
+                .collect(),
+            ScaleSequence::Groups(size) => run.windows(*size).flatten().cloned().collect(),
+        }
+    }
+
+    // The sequence up the run, down it, or up and back down without repeating the top note.
+    fn order(&self, run: &[NotePitch], direction: &RunDirection) -> Vec<NotePitch> {
+        let descending: Vec<NotePitch> = run.iter().rev().cloned().collect();
+        match direction {
+            RunDirection::Up => self.apply(run),
+            RunDirection::Down => self.apply(&descending),
+            RunDirection::UpAndDown => {
+                let mut notes = self.apply(run);
+                notes.extend(self.apply(&descending).into_iter().skip(1));
+                notes
+            }
+        }
+    }
+}
+
+// How play_chord sounds a chord: all at once, strummed across the strings either way, or a
+// tone at a time.
+#[derive(PartialEq, Clone, Debug)]
+enum ChordPlayback {
+    Block,
+    StrumDown,
+    StrumUp,
+    Arpeggio,
+}
+
+impl Display for ChordPlayback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ChordPlayback::Block => write!(f, "Block")?,
+            ChordPlayback::StrumDown => write!(f, "Strum down")?,
+            ChordPlayback::StrumUp => write!(f, "Strum up")?,
+            ChordPlayback::Arpeggio => write!(f, "Arpeggio")?,
+        }
+        Ok(())
+    }
+}
+
+impl ChordPlayback {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "BLOCK" | "" => ChordPlayback::Block,
+            "DOWN" | "STRUM DOWN" => ChordPlayback::StrumDown,
+            "UP" | "STRUM UP" => ChordPlayback::StrumUp,
+            "ARPEGGIO" => ChordPlayback::Arpeggio,
+            _ => {
+                println!("Enter a mode (Block, Down, Up, Arpeggio):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                ChordPlayback::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+}
+
+// The order an arpeggio takes the chord tones in: a run through them, or tones counted from 1
+// at the bottom with "." for a rest, e.g. "1 3 2 4". Counting past the top carries on an octave
+// up.
+#[derive(PartialEq, Clone, Debug)]
+enum ArpeggioPattern {
+    Run(RunDirection),
+    Tones(Vec<Option<usize>>),
+}
+
+impl Display for ArpeggioPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ArpeggioPattern::Run(direction) => write!(f, "{}", direction)?,
+            ArpeggioPattern::Tones(tones) => {
+                let tones: Vec<String> = tones
+                    .iter()
+                    .map(|tone| match tone {
+                        Some(tone) => (tone + 1).to_string(),
+                        None => ".".to_string(),
+                    })
+                    .collect();
+                write!(f, "{}", tones.join(" "))?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ArpeggioPattern {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "UP" => return ArpeggioPattern::Run(RunDirection::Up),
+            "DOWN" => return ArpeggioPattern::Run(RunDirection::Down),
+            "BOTH" => return ArpeggioPattern::Run(RunDirection::UpAndDown),
+            _ => (),
+        }
+        let tones: Vec<Option<Option<usize>>> = input
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != ',')
+            .map(|c| match c {
+                '.' => Some(None),
+                '1'..='9' => Some(c.to_digit(10).map(|digit| digit as usize - 1)),
+                _ => None,
+            })
+            .collect();
+        match tones.iter().all(|tone| tone.is_some()) && !tones.is_empty() {
+            true => ArpeggioPattern::Tones(tones.into_iter().flatten().collect()),
+            false => {
+                println!("Enter a pattern (Up, Down, Both, or tones e.g., 1 3 2 4):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                ArpeggioPattern::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+
+    // One event of step_secs per tone or rest, from pitches low to high.
+    fn events(&self, pitches: &[NotePitch], step_secs: f32) -> Vec<AudioEvent> {
+        let tones: Vec<Option<NotePitch>> = match self {
+            ArpeggioPattern::Run(direction) => {
+                direction.order(pitches).into_iter().map(Some).collect()
+            }
+            ArpeggioPattern::Tones(tones) => tones
+                .iter()
+                .map(|tone| {
+                    let tone = (*tone)?;
+                    let pitch = pitches.get(tone % pitches.len())?;
+                    let octaves = (tone / pitches.len()) as i16;
+                    Some(NotePitch::from_semitones(
+                        pitch.to_semitones() + octaves * 12,
+                    ))
+                })
+                .collect(),
+        };
+        tones
+            .iter()
+            .map(|tone| AudioEvent::new(tone.as_slice(), step_secs))
+            .collect()
+    }
+}
+
+// A pitch sounded within an event, with its own volume from 0 to 1, starting delay_secs after
+// the event does.
+#[derive(PartialEq, Clone, Debug)]
+struct AudioNote {
+    frequency: f32,
+    gain: f32,
+    delay_secs: f32,
+}
+
+// Notes started together and held for a number of seconds. No notes is a rest.
+#[derive(PartialEq, Clone, Debug)]
+struct AudioEvent {
+    notes: Vec<AudioNote>,
+    duration_secs: f32,
+}
+
+impl AudioEvent {
+    fn new(pitches: &[NotePitch], duration_secs: f32) -> Self {
+        let frequencies: Vec<f32> = pitches
+            .iter()
+            .map(|pitch| pitch.frequency() as f32)
+            .collect();
+        AudioEvent::from_frequencies(&frequencies, duration_secs)
+    }
+
+    fn from_frequencies(frequencies: &[f32], duration_secs: f32) -> Self {
+        AudioEvent {
+            notes: frequencies
+                .iter()
+                .map(|&frequency| AudioNote {
+                    frequency,
+                    gain: 1.0,
+                    delay_secs: 0.0,
+                })
+                .collect(),
+            duration_secs,
+        }
+    }
+
+    // The pitches in the order they're given, each spread_secs after the one before, as a
+    // strum across strings.
+    fn strum(pitches: &[NotePitch], spread_secs: f32, duration_secs: f32) -> Self {
+        let mut event = AudioEvent::new(pitches, duration_secs);
+        for (index, note) in event.notes.iter_mut().enumerate() {
+            note.delay_secs = index as f32 * spread_secs;
+        }
+        event
+    }
+}
+
+// A Karplus-Strong string: a burst of noise circulating through a delay line one period long,
+// losing a little energy and high end on every pass.
+struct PluckedString {
+    buffer: Vec<f32>,
+    position: usize,
+    delay: f32,
+    // How much of the sample before goes into the averaging; 0.5 is the classic, darkest filter.
+    blend: f32,
+    decay: f32,
+}
+
+impl PluckedString {
+    fn new(frequency: f32, sample_rate: f32, damping: f32, brightness: f32, seed: u32) -> Self {
+        let blend = 0.5 * (1.0 - brightness.clamp(0.0, 1.0));
+        // The averaging filter delays the loop by blend samples, so the line is that much shorter
+        // to keep the string in tune.
+        let delay = (sample_rate / frequency - blend).max(2.0);
+        let length = delay.ceil() as usize + 2;
+        let mut state = seed.max(1);
+        let mut buffer: Vec<f32> = (0..length)
+            .map(|_| {
+                // xorshift, enough for an excitation burst.
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as f32 / u32::MAX as f32 * 2.0 - 1.0
+            })
+            .collect();
+        let mean = buffer.iter().sum::<f32>() / length as f32;
+        buffer.iter_mut().for_each(|sample| *sample -= mean);
+        PluckedString {
+            buffer,
+            position: 0,
+            delay,
+            blend,
+            decay: 1.0 - damping.clamp(0.0, 1.0) * 0.0005,
+        }
+    }
+
+    // The sample delay samples ago, between buffer entries where the delay isn't whole.
+    fn read(&self, delay: f32) -> f32 {
+        let length = self.buffer.len();
+        let back = delay.floor() as usize;
+        let fraction = delay - back as f32;
+        let newer = self.buffer[(self.position + length - back) % length];
+        let older = self.buffer[(self.position + length - back - 1) % length];
+        newer + (older - newer) * fraction
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        let current = self.read(self.delay);
+        let previous = self.read(self.delay + 1.0);
+        let sample = self.decay * ((1.0 - self.blend) * current + self.blend * previous);
+        self.buffer[self.position] = sample;
+        self.position = (self.position + 1) % self.buffer.len();
+        sample
+    }
+}
+
+// One sounding note with its own oscillator or string, counted in frames from when it started
+// after waiting out its delay.
+struct Voice {
+    frequency: f32,
+    gain: f32,
+    phase: f32,
+    string: Option<PluckedString>,
+    delay_frames: usize,
+    elapsed_frames: usize,
+    held_frames: usize,
+}
+
+// Up to MAX_VOICES notes at once. A new note takes a finished voice, or the oldest one when
+// they're all sounding.
+struct Synth {
+    settings: SynthSettings,
+    sample_rate: f32,
+    voices: Vec<Voice>,
+    // Counts plucks so no two strings start from the same noise.
+    plucks: u32,
+}
+
+const MAX_VOICES: usize = 16;
+
+impl Synth {
+    fn new(settings: &SynthSettings, sample_rate: f32) -> Self {
+        Synth {
+            settings: settings.clone(),
+            sample_rate,
+            voices: Vec::new(),
+            plucks: 0,
+        }
+    }
+
+    // Delayed notes are still let go of at the end of held_secs, so a strum stops together.
+    fn note_on(&mut self, note: &AudioNote, held_secs: f32) {
+        let string = match self.settings.model {
+            VoiceModel::Oscillator => None,
+            VoiceModel::PluckedString {
+                damping,
+                brightness,
+            } => {
+                self.plucks += 1;
+                let seed = self.plucks.wrapping_mul(2654435761);
+                let sample_rate = self.sample_rate;
+                let string =
+                    PluckedString::new(note.frequency, sample_rate, damping, brightness, seed);
+                Some(string)
+            }
+        };
+        let held_secs = (held_secs - note.delay_secs).max(0.0);
+        let voice = Voice {
+            frequency: note.frequency,
+            gain: note.gain,
+            phase: 0.0,
+            string,
+            delay_frames: (note.delay_secs * self.sample_rate).round() as usize,
+            elapsed_frames: 0,
+            held_frames: (held_secs * self.sample_rate).round() as usize,
+        };
+        let finished = self.voices.iter().position(|voice| self.is_finished(voice));
+        let oldest = self
+            .voices
+            .iter()
+            .enumerate()
+            .max_by_key(|(_index, voice)| voice.elapsed_frames)
+            .map(|(index, _voice)| index);
+        match (finished, self.voices.len() < MAX_VOICES, oldest) {
+            (Some(index), _, _) => self.voices[index] = voice,
+            (None, true, _) | (None, false, None) => self.voices.push(voice),
+            (None, false, Some(index)) => self.voices[index] = voice,
+        }
+    }
+
+    fn is_finished(&self, voice: &Voice) -> bool {
+        let release = self.settings.envelope.release * self.sample_rate;
+        voice.delay_frames == 0 && voice.elapsed_frames as f32 >= voice.held_frames as f32 + release
+    }
+
+    fn is_silent(&self) -> bool {
+        self.voices.iter().all(|voice| self.is_finished(voice))
+    }
+
+    // The next sample of every voice mixed together, softly limited so loud chords don't clip.
+    fn next_sample(&mut self) -> f32 {
+        let settings = &self.settings;
+        let mut mix = 0.0;
+        for voice in &mut self.voices {
+            if voice.delay_frames > 0 {
+                voice.delay_frames -= 1;
+                continue;
+            }
+            let elapsed = voice.elapsed_frames as f32 / self.sample_rate;
+            let held = voice.held_frames as f32 / self.sample_rate;
+            let level = settings.envelope.level(elapsed, held);
+            // A string keeps ringing in its delay line even while the envelope is closed.
+            let sample = match &mut voice.string {
+                Some(string) => string.next_sample(),
+                None => settings.waveform.sample(voice.phase),
+            };
+            if level > 0.0 {
+                mix += sample * level * voice.gain;
+            }
+            voice.phase = (voice.phase + voice.frequency / self.sample_rate).fract();
+            voice.elapsed_frames += 1;
+        }
+        (mix * settings.gain).tanh()
+    }
+}
+
+// Starts each event's notes when the one before has had its time, then lets the last notes
+// ring out through their release. Both the output stream and offline rendering play through it.
+struct Sequencer {
+    synth: Synth,
+    events: Vec<AudioEvent>,
+    next_event: usize,
+    frames_left: usize,
+    // Starts over from the first event instead of finishing, until the stream is dropped.
+    looping: bool,
+}
+
+impl Sequencer {
+    fn new(settings: &SynthSettings, sample_rate: f32, events: Vec<AudioEvent>) -> Self {
+        Sequencer {
+            synth: Synth::new(settings, sample_rate),
+            events,
+            next_event: 0,
+            frames_left: 0,
+            looping: false,
+        }
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        if self.looping && self.frames_left == 0 && self.next_event == self.events.len() {
+            self.next_event = 0;
+        }
+        while self.frames_left == 0 && self.next_event < self.events.len() {
+            let event = &self.events[self.next_event];
+            for note in &event.notes {
+                self.synth.note_on(note, event.duration_secs);
+            }
+            self.frames_left = (event.duration_secs * self.synth.sample_rate).round() as usize;
+            self.next_event += 1;
+        }
+        self.frames_left = self.frames_left.saturating_sub(1);
+        self.synth.next_sample()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.next_event == self.events.len() && self.frames_left == 0 && self.synth.is_silent()
+    }
+
+    // Fills interleaved frames with the same sample on every channel.
+    fn fill(&mut self, data: &mut [f32], channels: usize) {
+        for frame in data.chunks_mut(channels) {
+            frame.fill(self.next_sample());
+        }
+    }
+
+    // Seconds from the first event until the last note has been released.
+    fn duration_secs(&self) -> f32 {
+        let events: f32 = self.events.iter().map(|event| event.duration_secs).sum();
+        events + self.synth.settings.envelope.release
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+struct TimeSignature {
+    beats: usize,
+    unit: usize,
+}
+
+impl Display for TimeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}/{}", self.beats, self.unit)
+    }
+}
+
+impl TimeSignature {
+    fn from_string(input: String) -> Self {
+        let parsed = input
+            .split_once('/')
+            .map(|(beats, unit)| (beats.trim().parse::<usize>(), unit.trim().parse::<usize>()));
+        match parsed {
+            Some((Ok(beats @ 1..=16), Ok(unit @ (1 | 2 | 4 | 8 | 16)))) => {
+                TimeSignature { beats, unit }
+            }
+            _ => {
+                println!("Enter a time signature (e.g., 4/4, 3/4, 6/8):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                TimeSignature::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+}
+
+// How a beat of the bar clicks: "X" accented, "x" plain or "." silent.
+#[derive(PartialEq, Clone, Debug)]
+enum Accent {
+    Strong,
+    Normal,
+    Silent,
+}
+
+impl Display for Accent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Accent::Strong => write!(f, "X")?,
+            Accent::Normal => write!(f, "x")?,
+            Accent::Silent => write!(f, ".")?,
+        }
+        Ok(())
+    }
+}
+
+impl Accent {
+    fn parse(input: char) -> Option<Self> {
+        match input {
+            'X' => Some(Accent::Strong),
+            'x' => Some(Accent::Normal),
+            '.' => Some(Accent::Silent),
+            _ => None,
+        }
+    }
+
+    // A strong first beat and plain ones after it.
+    fn default_pattern(beats: usize) -> Vec<Accent> {
+        (0..beats)
+            .map(|beat| match beat {
+                0 => Accent::Strong,
+                _ => Accent::Normal,
+            })
+            .collect()
+    }
+}
+
+// Clicks for each beat of the unit in the time signature, with the beat split into
+// subdivisions that click more quietly. The BPM counts beats of the unit, the same beats that
+// are tapped for tap tempo. It runs in the background once started.
+struct Metronome {
+    bpm: f32,
+    time_signature: TimeSignature,
+    accents: Vec<Accent>,
+    subdivisions: usize,
+    running: Option<Arc<AtomicBool>>,
+}
+
+impl Display for Metronome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let accents: Vec<String> = self
+            .accents
+            .iter()
+            .map(|accent| accent.to_string())
+            .collect();
+        let state = match self.is_running() {
+            true => "running",
+            false => "stopped",
+        };
+        write!(
+            f,
+            "{} BPM in {}, accents {}, {} subdivision(s) per beat, {}",
+            self.bpm,
+            self.time_signature,
+            accents.join(""),
+            self.subdivisions,
+            state
+        )
+    }
+}
+
+const METRONOME_ACCENT_HZ: f32 = 1760.0;
+const METRONOME_BEAT_HZ: f32 = 1320.0;
+
+impl Metronome {
+    fn new() -> Self {
+        Metronome {
+            bpm: 120.0,
+            time_signature: TimeSignature { beats: 4, unit: 4 },
+            accents: Accent::default_pattern(4),
+            subdivisions: 1,
+            running: None,
+        }
+    }
+
+    // A short sine blip that dies away well before the next click.
+    fn click_settings() -> SynthSettings {
+        SynthSettings {
+            model: VoiceModel::Oscillator,
+            waveform: Waveform::Sine,
+            envelope: Envelope {
+                attack: 0.001,
+                decay: 0.04,
+                sustain: 0.0,
+                release: 0.01,
+            },
+            gain: 0.6,
+        }
+    }
+
+    // One bar of clicks, which the engine loops.
+    fn bar(&self) -> Vec<AudioEvent> {
+        let step_secs = 60.0 / self.bpm / self.subdivisions as f32;
+        let mut events: Vec<AudioEvent> = Vec::new();
+        for accent in &self.accents {
+            for subdivision in 0..self.subdivisions {
+                let click = match (accent, subdivision) {
+                    (Accent::Silent, _) => None,
+                    (Accent::Strong, 0) => Some((METRONOME_ACCENT_HZ, 1.0)),
+                    (Accent::Normal, 0) => Some((METRONOME_BEAT_HZ, 0.7)),
+                    _ => Some((METRONOME_BEAT_HZ, 0.35)),
+                };
+                let notes = click
+                    .map(|(frequency, gain)| AudioNote {
+                        frequency,
+                        gain,
+                        delay_secs: 0.0,
+                    })
+                    .into_iter()
+                    .collect();
+                events.push(AudioEvent {
+                    notes,
+                    duration_secs: step_secs,
+                });
+            }
+        }
+        events
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+            .as_ref()
+            .is_some_and(|running| running.load(std::sync::atomic::Ordering::Relaxed))
+    }
+
+    fn start(&mut self, audio_engine: &AudioEngine) {
+        self.stop();
+        self.running = audio_engine.start_loop(&Metronome::click_settings(), self.bar());
+    }
+
+    fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.store(false, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    // Beats per minute from the average gap between the last few taps.
+    fn tap_tempo(taps: &[Instant]) -> Option<f32> {
+        let taps = &taps[taps.len().saturating_sub(8)..];
+        let first = taps.first()?;
+        let last = taps.last()?;
+        let gaps = taps.len().checked_sub(1).filter(|&gaps| gaps > 0)?;
+        let gap_secs = last.duration_since(*first).as_secs_f32() / gaps as f32;
+        Some((60.0 / gap_secs).round().clamp(20.0, 300.0))
+    }
+}
+
+#[derive(Clone)]
+struct AudioEngine {
+    // None without sound hardware, where audio can still be rendered to files.
+    device: Option<Arc<Device>>,
+    config: StreamConfig,
+    settings: SynthSettings,
+}
+
+impl AudioEngine {
+    fn new() -> Self {
+        let host = cpal::default_host();
+        let device = host.default_output_device().map(Arc::new);
+        let config = StreamConfig {
+            channels: 2,
+            sample_rate: SampleRate(44100),
+            buffer_size: BufferSize::Default,
+        };
+        AudioEngine {
+            device,
+            config,
+            settings: SynthSettings::new(),
+        }
+    }
+
+    async fn play_audio(&self, frequencies: Vec<f32>, duration_secs: f32) {
+        let event = AudioEvent::from_frequencies(&frequencies, duration_secs);
+        self.play_events(vec![event]).await;
+    }
+
+    async fn play_events(&self, events: Vec<AudioEvent>) {
+        let Some(device) = self.device.clone() else {
+            println!("No output device available");
+            return;
+        };
+        let config = self.config.clone();
+        let sample_rate = config.sample_rate.0 as f32;
+        let sequencer = Sequencer::new(&self.settings, sample_rate, events);
+        let duration_secs = sequencer.duration_secs();
+
+        tokio::task::spawn_blocking(move || {
+            let Some(_stream) = AudioEngine::open_stream(&device, &config, sequencer) else {
+                return;
+            };
+            std::thread::sleep(Duration::from_secs_f32(duration_secs));
+        })
+        .await
+        .expect("Audio task failed");
+    }
+
+    // Plays the events over and over in the background with their own settings, until the
+    // returned flag is cleared. The flag is cleared too if the stream can't be opened.
+    fn start_loop(
+        &self,
+        settings: &SynthSettings,
+        events: Vec<AudioEvent>,
+    ) -> Option<Arc<AtomicBool>> {
+        let Some(device) = self.device.clone() else {
+            println!("No output device available");
+            return None;
+        };
+        let config = self.config.clone();
+        let mut sequencer = Sequencer::new(settings, config.sample_rate.0 as f32, events);
+        sequencer.looping = true;
+        let running = Arc::new(AtomicBool::new(true));
+        let flag = running.clone();
+        std::thread::spawn(move || {
+            let stream = AudioEngine::open_stream(&device, &config, sequencer);
+            while stream.is_some() && flag.load(std::sync::atomic::Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            flag.store(false, std::sync::atomic::Ordering::Relaxed);
+        });
+        Some(running)
+    }
+
+    // A playing stream fed by the sequencer, which stops when it's dropped.
+    fn open_stream(
+        device: &Device,
+        config: &StreamConfig,
+        mut sequencer: Sequencer,
+    ) -> Option<cpal::Stream> {
+        let channels = config.channels as usize;
+        let stream = device.build_output_stream(
+            config,
+            move |data: &mut [f32], _: &OutputCallbackInfo| sequencer.fill(data, channels),
+            move |err| {
+                eprintln!("An error occurred on the audio stream: {:?}", err);
+            },
+            None,
+        );
+        // Hardware can still refuse the stream, which shouldn't take the app down with it.
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                println!("Couldn't open the output stream: {:?}", error);
+                return None;
+            }
+        };
+        if let Err(error) = stream.play() {
+            println!("Couldn't play the stream: {:?}", error);
+            return None;
+        }
+        Some(stream)
+    }
+
+    // The events one after another as interleaved samples, without an output device.
+    fn render(&self, events: &[AudioEvent]) -> Vec<f32> {
+        let sample_rate = self.config.sample_rate.0 as f32;
+        let channels = self.config.channels as usize;
+        let mut sequencer = Sequencer::new(&self.settings, sample_rate, events.to_vec());
+        let mut samples: Vec<f32> = Vec::new();
+        let mut frame = vec![0.0; channels];
+        while !sequencer.is_finished() {
+            sequencer.fill(&mut frame, channels);
+            samples.extend(&frame);
+        }
+        samples
+    }
+
+    fn samples_to_secs(&self, samples: usize) -> f32 {
+        samples as f32 / self.config.channels as f32 / self.config.sample_rate.0 as f32
+    }
+
+    // 16 bit PCM at the engine's sample rate and channel count.
+    fn write_wav(&self, path: &str, samples: &[f32]) -> io::Result<()> {
+        let channels = self.config.channels as u32;
+        let sample_rate = self.config.sample_rate.0;
+        let data_size = samples.len() as u32 * 2;
+        let mut bytes: Vec<u8> = Vec::with_capacity(44 + data_size as usize);
+        bytes.extend(b"RIFF");
+        bytes.extend((36 + data_size).to_le_bytes());
+        bytes.extend(b"WAVEfmt ");
+        bytes.extend(16u32.to_le_bytes());
+        // Uncompressed PCM.
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend((channels as u16).to_le_bytes());
+        bytes.extend(sample_rate.to_le_bytes());
+        bytes.extend((sample_rate * channels * 2).to_le_bytes());
+        bytes.extend((channels as u16 * 2).to_le_bytes());
+        bytes.extend(16u16.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend(data_size.to_le_bytes());
+        for sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            bytes.extend(value.to_le_bytes());
+        }
+        std::fs::write(path, bytes)
+    }
+}
+
+struct Attempt<T, U> {
+    correct_answer: U,
+    attempt_answer: T,
+    correct: bool,
+    attempt_number: u64,
+    time_since_last_attempt: Option<u64>,
+    elapsed_time: u64,
+    attempt_time: NaiveDateTime,
+    game_number: u64,
+}
+
+impl<T: Ord, U: Ord> Attempt<T, U> {
+    fn new(
+        correct_answer: U,
+        attempt_answer: T,
+        attempt_number: u64,
+        time_since_last_attempt: Option<Duration>,
+        elapsed_time: Duration,
+        game_number: u64,
+    ) -> Self {
+        //let correct = correct_answer == attempt_answer;
+        let attempt_time = Utc::now().naive_utc();
+        match time_since_last_attempt {
+            Some(time_since) => Attempt {
+                correct_answer,
+                attempt_answer,
+                correct: false,
+                attempt_number,
+                time_since_last_attempt: Some(time_since.as_secs()),
+                elapsed_time: elapsed_time.as_secs(),
+                attempt_time,
+                game_number,
+            },
+            None => Attempt {
+                correct_answer,
+                attempt_answer,
+                correct: false,
+                attempt_number,
+                time_since_last_attempt: None,
+                elapsed_time: elapsed_time.as_secs(),
+                attempt_time,
+                game_number,
+            },
+        }
+    }
+
+    fn deserialize_time(&self) -> (Option<Duration>, Duration, NaiveDateTime) {
+        match self.time_since_last_attempt {
+            Some(time_since) => (
+                Some(Duration::from_secs(time_since)),
+                Duration::from_secs(self.elapsed_time),
+                self.attempt_time,
+            ),
+            None => (
+                None,
+                Duration::from_secs(self.elapsed_time),
+                self.attempt_time,
+            ),
+        }
+    }
+}
+
+struct Game<T, U> {
+    game_number: u64,
+    possible_notes: Vec<U>,
+    attempted_notes: Vec<T>,
+    attempts: Vec<Attempt<T, U>>,
+    start_time: NaiveDateTime,
+    end_time: Option<NaiveDateTime>,
+}
+
+impl<T, U> Game<T, U> {
+    fn new(possible_notes: Vec<U>, game_number: u64) -> Self {
+        Game {
+            game_number,
+            possible_notes,
+            attempted_notes: Vec::new(),
+            attempts: Vec::new(),
+            start_time: Utc::now().naive_utc(),
+            end_time: None,
+        }
+    }
+}
+
+struct EarTraining<T, U> {
+    audio_engine: AudioEngine,
+    game: Game<T, U>,
+}
+
+impl<T, U> EarTraining<T, U> {
+    fn new_notepitch_notepitch(
+        audio_engine: &AudioEngine,
+        possible_notes: &Vec<NotePitch>,
+    ) -> EarTraining<NotePitch, NotePitch> {
+        EarTraining {
+            audio_engine: audio_engine.clone(),
+            game: Game::new(possible_notes.clone(), 1),
+        }
+    }
+    fn new_notename_notepitch(
+        audio_engine: &AudioEngine,
+        possible_notes: &Vec<NotePitch>,
+    ) -> EarTraining<NoteName, NotePitch> {
+        EarTraining {
+            audio_engine: audio_engine.clone(),
+            game: Game::new(possible_notes.clone(), 1),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum CompareLayout {
+    Stacked,
+    SideBySide,
+}
+
+impl Display for CompareLayout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            CompareLayout::Stacked => write!(f, "Stacked")?,
+            CompareLayout::SideBySide => write!(f, "Side by side")?,
+        }
+        Ok(())
+    }
+}
+
+impl CompareLayout {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "STACKED" | "" => CompareLayout::Stacked,
+            "SIDE" | "SIDE BY SIDE" => CompareLayout::SideBySide,
+            _ => {
+                println!("Enter a layout (Stacked, Side):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                CompareLayout::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+}
+
+// How fret_ninja draws its progression: wrapped into a grid, in one row cut to the terminal, or
+// each neck in full one after another.
+#[derive(PartialEq, Clone, Debug)]
+enum ProgressionLayout {
+    Grid,
+    Columns,
+    Stacked,
+}
+
+impl Display for ProgressionLayout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            ProgressionLayout::Grid => write!(f, "Grid")?,
+            ProgressionLayout::Columns => write!(f, "Columns")?,
+            ProgressionLayout::Stacked => write!(f, "Stacked")?,
+        }
+        Ok(())
+    }
+}
+
+impl ProgressionLayout {
+    fn from_string(input: String) -> Self {
+        match input.to_uppercase().as_str() {
+            "GRID" | "" => ProgressionLayout::Grid,
+            "COLUMNS" => ProgressionLayout::Columns,
+            "STACKED" => ProgressionLayout::Stacked,
+            _ => {
+                println!("Enter a layout (Grid, Columns, Stacked):");
+                let mut reinput = String::new();
+                io::stdin()
+                    .read_line(&mut reinput)
+                    .expect("Failed to read input");
+                ProgressionLayout::from_string(reinput.trim().to_string())
+            }
+        }
+    }
+}
+
+struct DisplayGroup {
+    instrument: Instrument,
+    key: NoteName,
+    notes: Vec<NoteName>,
+    chord: Option<Chord>,
+    // The chord's voicing on the instrument, found again whenever either changes.
+    voicing: Option<Voicing>,
+    scale: Option<Scale>,
+    render_options: RenderOptions,
+    // Exact (string, fret) positions picked on the neck; notes holds their names.
+    toggled: Vec<(usize, usize)>,
+    suggestions: Suggestions,
+}
+
+// The names of the chords and scales that fit a set of notes, kept until the notes change.
+#[derive(Default)]
+struct Suggestions {
+    notes: Vec<NoteName>,
+    chords: Vec<String>,
+    scales: Vec<String>,
+}
+
+impl Display for DisplayGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.render(&self.render_options))
+    }
+}
+
+impl DisplayGroup {
+    fn update_voicing(&mut self) {
+        self.voicing = self
+            .chord
+            .as_ref()
+            .and_then(|chord| self.instrument.find_voicing(chord));
+    }
+
+    fn shows_chord_box(&self, options: &RenderOptions) -> bool {
+        options.orientation == Orientation::Vertical && self.voicing.is_some()
+    }
+
+    // A chord box for a chord on a vertical neck, otherwise the fretboard.
+    fn render(&self, options: &RenderOptions) -> String {
+        match &self.voicing {
+            Some(voicing) if options.orientation == Orientation::Vertical => {
+                ChordBoxView::new(&self.instrument, voicing, options).to_string()
+            }
+            _ => self.fretboard_view(options).to_string(),
+        }
+    }
+
+    fn fretboard_view<'a>(&'a self, options: &'a RenderOptions) -> FretboardView<'a> {
+        let root = match &self.chord {
+            Some(chord) => &chord.notes[0],
+            None => &self.key,
+        };
+        let fingering = self.voicing.as_ref().map(|voicing| voicing.fingering());
+        FretboardView::new(&self.instrument, options, root, fingering)
+    }
+
+    // The frets around the chord's voicing, taking in the open strings when it sits by the nut.
+    fn chord_window(&self) -> FretWindow {
+        match &self.voicing {
+            Some(voicing) => {
+                let first = voicing.first_fret();
+                let last = first + voicing.fret_span() - 1;
+                match first {
+                    1 => FretWindow::Range(0, last),
+                    _ => FretWindow::Range(first, last),
+                }
+            }
+            None => FretWindow::Fit,
+        }
+    }
+
+    fn pane_options(&self, width: usize, height: usize, offset: usize) -> RenderOptions {
+        self.render_options
+            .pane(&self.instrument, width, height, offset)
+    }
+
+    // Highlights the chosen notes, or the whole neck when there are none. Toggled positions
+    // stand out over the rest of their notes for as long as the notes are still theirs.
+    fn refresh(&mut self) {
+        if self.toggled_notes() != self.notes {
+            self.toggled.clear();
+        }
+        match (self.notes.len(), self.toggled.len()) {
+            (0, _) => self.instrument.show_all(),
+            (_, 0) => self.instrument.show_notes(&self.notes),
+            _ => {
+                let notes = self
+                    .instrument
+                    .notes_layer("notes", &HighlightStyle::Dim, &self.notes);
+                let toggled =
+                    HighlightLayer::new("toggled", &HighlightStyle::Bold, self.toggled.clone());
+                self.instrument.layers = vec![notes, toggled];
+            }
+        }
+    }
+
+    fn update_suggestions(&mut self) {
+        if self.suggestions.notes == self.notes {
+            return;
+        }
+        self.suggestions = Suggestions {
+            notes: self.notes.clone(),
+            chords: Chord::fitting(&self.notes)
+                .into_iter()
+                .map(|chord| chord.name)
+                .collect(),
+            scales: Scale::fitting(&self.notes)
+                .into_iter()
+                .map(|scale| scale.name)
+                .collect(),
+        };
+    }
+
+    // The names of the toggled positions, sorted like notes.
+    fn toggled_notes(&self) -> Vec<NoteName> {
+        let mut notes: Vec<NoteName> = self
+            .toggled
+            .iter()
+            .map(|&(i, j)| self.instrument.fretboard[i][j].note_pitch.note_name.clone())
+            .collect();
+        notes.sort();
+        notes.dedup();
+        notes
+    }
+
+    fn toggle(&mut self, (i, j): (usize, usize)) -> String {
+        let pitch = Theme::printable(&self.instrument.fretboard[i][j].note_pitch.get_name());
+        let action = match self.toggled.iter().position(|&position| position == (i, j)) {
+            Some(index) => {
+                self.toggled.remove(index);
+                "Removed"
+            }
+            None => {
+                self.toggled.push((i, j));
+                "Added"
+            }
+        };
+        self.notes = self.toggled_notes();
+        self.refresh();
+        format!(
+            "{} {} on string {} fret {}",
+            action,
+            pitch,
+            self.instrument.string_count - i,
+            j
+        )
+    }
+
+    fn details(&self) -> Vec<String> {
+        let instrument = &self.instrument;
+        let open_notes: Vec<String> = instrument.tuning.iter().map(|n| n.to_string()).collect();
+        vec![
+            format!("Type: {}", instrument.instrument_type),
+            format!("Number of strings: {}", instrument.string_count),
+            format!("Number of frets: {}", instrument.fret_count),
+            format!("Scale length: {}", instrument.scale_length),
+            format!(
+                "Tuning: {} {}",
+                instrument.root_note.note_name, instrument.tuning_type
+            ),
+            format!("Open Notes: {}", open_notes.join(" ")),
+        ]
+    }
+
+    fn set_chord(&mut self, chord: Chord) -> String {
+        self.key = chord.notes[0].clone();
+        self.notes = chord.notes.clone();
+        self.notes.sort();
+        let message = format!("Chord changed to {}", chord.name);
+        self.chord = Some(chord);
+        self.update_voicing();
+        self.refresh();
+        message
+    }
+
+    fn set_scale(&mut self, scale: Scale) -> String {
+        self.key = scale.notes[0].clone();
+        self.notes = scale.notes.clone();
+        self.notes.sort();
+        let message = format!("Scale changed to {}", scale.name);
+        self.scale = Some(scale);
+        self.refresh();
+        message
+    }
+
+    // Moves the chord and scale to the new key, keeping whichever was on display.
+    fn set_key(&mut self, key: &NoteName) -> String {
+        let shown = self.chord_or_scale().map(|(_, _, for_scale)| for_scale);
+        self.key = key.clone();
+        self.chord = self.chord.as_ref().map(|c| Chord::new(key, &c.definition));
+        self.update_voicing();
+        self.scale = self.scale.as_ref().map(|s| Scale::new(key, &s.definition));
+        match (shown, &self.chord, &self.scale) {
+            (Some(false), Some(chord), _) => self.notes = chord.notes.clone(),
+            (Some(true), _, Some(scale)) => self.notes = scale.notes.clone(),
+            _ => (),
+        }
+        self.notes.sort();
+        self.refresh();
+        format!("Key changed to {}", Theme::printable(&key.get_name()))
+    }
+
+    fn shift_key(&mut self, semitones: i8) -> String {
+        let key = NoteName::from_number((self.key.to_number() + semitones).rem_euclid(12));
+        self.set_key(&key)
+    }
+
+    // Steps through the chords in the current key, starting from the first.
+    fn cycle_chord(&mut self, step: i8) -> String {
+        let number = match self.chord.as_ref().and_then(|chord| chord.number()) {
+            Some(number) => (number + step).rem_euclid(24),
+            None => 0,
+        };
+        self.set_chord(Chord::from_number(&self.key, number))
+    }
+
+    fn cycle_scale(&mut self, step: i8) -> String {
+        let number = match self.scale.as_ref().and_then(|scale| scale.number()) {
+            Some(number) => (number + step).rem_euclid(17),
+            None => 0,
+        };
+        self.set_scale(Scale::from_number(&self.key, number))
+    }
+
+    fn cycle_tuning_type(&mut self, step: i8) -> String {
+        if !self.instrument.has_tuning_types() {
+            return "Only standard guitars and basses have tuning types to cycle".to_string();
+        }
+        let types = [
+            TuningType::Standard,
+            TuningType::DropTuning,
+            TuningType::Open,
+        ];
+        let index = types
+            .iter()
+            .position(|tuning_type| *tuning_type == self.instrument.tuning_type)
+            .unwrap_or(0);
+        let tuning_type = &types[(index as i8 + step).rem_euclid(types.len() as i8) as usize];
+        let root_note = self.instrument.root_note.clone();
+        self.instrument.retune(tuning_type, &root_note);
+        self.update_voicing();
+        self.refresh();
+        format!(
+            "Tuning changed to {} {}. {}",
+            Theme::printable(&root_note.note_name.get_name()),
+            tuning_type,
+            self.instrument.tension_summary()
+        )
+    }
+
+    fn transpose_tuning(&mut self, semitones: i16) -> String {
+        self.instrument.transpose(semitones);
+        self.update_voicing();
+        self.refresh();
+        let open_notes: Vec<String> = self
+            .instrument
+            .tuning
+            .iter()
+            .map(|pitch| Theme::printable(&pitch.get_name()))
+            .collect();
+        format!(
+            "Tuning changed to {}. {}",
+            open_notes.join(" "),
+            self.instrument.tension_summary()
+        )
+    }
+
+    // Draws what the Display impl prints: a chord box for a chord on a vertical neck, otherwise
+    // the fretboard.
+    fn to_svg(&self, size: &SvgSize) -> SvgDiagram {
+        let options = &self.render_options;
+        let title = match (&self.chord, &self.scale) {
+            (Some(chord), _) => chord.name.clone(),
+            (None, Some(scale)) => scale.name.clone(),
+            (None, None) => {
+                let names: Vec<String> = self.notes.iter().map(|note| note.get_name()).collect();
+                names.join(" ")
+            }
+        };
+        if let (Orientation::Vertical, Some(voicing)) = (&options.orientation, &self.voicing) {
+            return ChordBoxView::new(&self.instrument, voicing, options).to_svg(&title, size);
+        }
+        self.fretboard_view(options).to_svg(&title, size)
+    }
+
+    // The chord wins when its notes are the ones on display, otherwise the scale. Gives the
+    // notes, the name and whether they came from the scale.
+    fn chord_or_scale(&self) -> Option<(Vec<NoteName>, String, bool)> {
+        let chord_shown = match &self.chord {
+            Some(chord) => {
+                let mut notes = chord.notes.clone();
+                notes.sort();
+                notes == self.notes
+            }
+            None => false,
+        };
+        match (&self.chord, &self.scale) {
+            (Some(chord), None) => Some((chord.notes.clone(), chord.short_name.clone(), false)),
+            (Some(chord), Some(_)) if chord_shown => {
+                Some((chord.notes.clone(), chord.short_name.clone(), false))
+            }
+            (_, Some(scale)) => Some((scale.notes.clone(), scale.name.clone(), true)),
+            (None, None) => None,
+        }
+    }
+
+    // Starts from the instrument saved as "default" when there is one.
+    fn new() -> Self {
+        let instrument = match DisplayGroup::default_instrument() {
+            Ok(instrument) => instrument,
+            Err(_) => Instrument::new(
+                &InstrumentType::Guitar,
+                &TuningType::Standard,
+                &NotePitch::new(&NaturalNote::C, &None, 2),
+                6,
+                24,
+            ),
+        };
+        let key = NoteName::new(&NaturalNote::C, &None);
+        let notes: Vec<NoteName> = Vec::new();
+        DisplayGroup {
+            instrument,
+            key,
+            notes,
+            chord: None,
+            voicing: None,
+            scale: None,
+            render_options: RenderOptions::new(),
+            toggled: Vec::new(),
+            suggestions: Suggestions::default(),
+        }
+    }
+
+    fn default_instrument() -> io::Result<Instrument> {
+        ConfigKind::Instrument
+            .load::<InstrumentDefinition>("default")
+            .and_then(|definition| definition.to_instrument())
+    }
+}
+
+const INTERACTIVE_HELP: &str =
+    "k/K key  c/C chord  s/S scale  t/T tuning  u/U tune up/down  o orientation  l labels  [/] scroll  arrows/click move  space toggle  x untoggle  : command  q quit";
+
+// How many fitting chords and scales the sidebar lists.
+const SUGGESTION_COUNT: usize = 4;
+
+// What interactive mode keeps between frames: a command being typed, the last message, how far
+// the neck is scrolled from the start of the fret window and the (string, fret) under the cursor.
+struct InteractiveState {
+    input: Option<String>,
+    status: String,
+    fret_offset: usize,
+    cursor: Option<(usize, usize)>,
+}
+
+// Raw mode on the alternate screen for as long as it lives, so the terminal is given back even
+// when drawing fails part way.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        crossterm::execute!(
+            io::stdout(),
+            crossterm::terminal::EnterAlternateScreen,
+            crossterm::cursor::Hide,
+            crossterm::event::EnableMouseCapture
+        )?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(
+            io::stdout(),
+            crossterm::event::DisableMouseCapture,
+            crossterm::cursor::Show,
+            crossterm::terminal::LeaveAlternateScreen
+        );
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+struct Runtime {
+    displays: Vec<DisplayGroup>,
+    display: DisplayGroup,
+    audio_engine: AudioEngine,
+    metronome: Metronome,
+}
+
+impl Runtime {
+    fn new() -> Self {
+        let mut displays: Vec<DisplayGroup> = Vec::new();
+        for _i in 0..=3 {
+            let display = DisplayGroup::new();
+            displays.push(display);
+        }
+        let display = DisplayGroup::new();
+        if let Err(error) = DisplayGroup::default_instrument() {
+            if error.kind() != io::ErrorKind::NotFound {
+                println!("Couldn't load the default instrument: {}", error);
+            }
+        }
+        let audio_engine: AudioEngine = AudioEngine::new();
+        Theme::set(&Theme::detect());
+        Runtime {
+            displays,
+            display,
+            audio_engine,
+            metronome: Metronome::new(),
+        }
+    }
+
+    async fn start(&mut self) {
+        self.show_details().await;
+        loop {
+            self.show_notes_displayed();
+            println!("\n{}", self.display);
+            println!("\nMenu:");
+            println!("1 - Fret Ninja");
+            println!("2 - Choose Key");
+            println!("3 - Choose Chord");
+            println!("4 - Choose Scale");
+            println!("5 - Add Note(s)");
+            println!("6 - Remove Note(s)");
+            println!("7 - Find Scales");
+            println!("8 - Find Chords");
+            println!("9 - Display Full Instrument");
+            println!("10 - Show Details");
+            println!("11 - Change Instrument Tuning");
+            println!("12 - Change Display Options");
+            println!("13 - Change Theme");
+            println!("14 - CAGED Shapes");
+            println!("15 - Scale Patterns");
+            println!("16 - Find Pitch(es)");
+            println!("17 - Highlight Layers");
+            println!("18 - Fret Calculator");
+            println!("19 - String Tension");
+            println!("20 - Natural Harmonics");
+            println!("21 - Sessions");
+            println!("22 - Instruments");
+            println!("23 - Compare Instruments");
+            println!("24 - Export SVG");
+            println!("25 - Export Chord Sheet");
+            println!("26 - Export Tab");
+            println!("27 - Interactive Mode");
+            println!("28 - Export Audio");
+            println!("29 - Sound Settings");
+            println!("30 - Play Chord");
+            println!("31 - Play Scale");
+            println!("32 - Metronome");
+            println!("0 - Exit");
+            println!("Enter your choice:");
+
+            let mut input: String = String::new();
+            io::stdin()
+                .read_line(&mut input)
+                .expect("Failed to read input");
+
+            match input.trim() {
+                "1" => self.fret_ninja().await,
+                "2" => self.choose_key().await,
+                "3" => self.choose_chord().await,
+                "4" => self.choose_scale().await,
+                "5" => self.add_notes().await,
+                "6" => self.remove_notes().await,
+                "7" => self.find_scales().await,
+                "8" => self.find_chords().await,
+                "9" => self.display_full_instrument().await,
+                "10" => self.show_details().await,
+                "11" => self.change_tuning().await,
+                "12" => self.change_display_options().await,
+                "13" => self.change_theme().await,
+                "14" => self.caged_shapes().await,
+                "15" => self.scale_patterns().await,
+                "16" => self.find_pitches().await,
+                "17" => self.highlight_layers().await,
+                "18" => self.fret_calculator().await,
+                "19" => self.string_tension().await,
+                "20" => self.natural_harmonics().await,
+                "21" => self.sessions().await,
+                "22" => self.instruments().await,
+                "23" => self.compare_instruments().await,
+                "24" => self.export_svg().await,
+                "25" => self.export_chord_sheet().await,
+                "26" => self.export_tab().await,
+                "27" => self.interactive().await,
+                "28" => self.export_audio().await,
+                "29" => self.sound_settings().await,
+                "30" => self.play_chord().await,
+                "31" => self.play_scale().await,
+                "32" => self.metronome().await,
+                "0" => {
+                    println!("Exiting...");
+                    break;
+                }
+                _ => println!("Invalid choice, please try again."),
+            }
+        }
+    }
+
+    async fn remove_notes(&mut self) {
+        println!("Enter Notes you wish to remove seperated by commas");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let note_strs: Vec<&str> = input.split(',').collect();
+        for note_str in note_strs {
+            if let Some(index) = self
+                .display
+                .notes
+                .iter()
+                .position(|x| x == &NoteName::from_string(note_str.trim().to_string()))
+            {
+                self.display.notes.remove(index);
+            } else {
+                println!(
+                    "{} not in notes displayed",
+                    NoteName::from_string(note_str.trim().to_string())
+                )
+            }
+        }
+        self.display.instrument.show_notes(&self.display.notes);
+    }
+
+    fn show_notes_displayed(&mut self) {
+        match self.display.notes.len() {
+            0 => (),
+            _ => {
+                print!("The notes you have selected are: ");
+                for note in &self.display.notes {
+                    print!("{} ", note);
+                }
+                print!("\n");
+            }
+        }
+    }
+
+    async fn add_notes(&mut self) {
+        println!("Enter Notes you wish to add seperated by commas");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let note_strs: Vec<&str> = input.split(',').collect();
+        let mut notes: Vec<NoteName> = Vec::new();
+        for note_str in note_strs {
+            notes.push(NoteName::from_string(note_str.trim().to_string()));
+        }
+        for note in &notes {
+            self.display.notes.push(note.clone());
+        }
+        self.display.notes.sort();
+        self.display.instrument.show_notes(&self.display.notes);
+    }
+
+    async fn fret_ninja(&mut self) {
+        println!("Choose Chord progession seperated by commas");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let mut chords = Vec::new();
+        let input_splits = Runtime::split_input_advanced(input);
+        let mut key_current = NoteName::new(&NaturalNote::C, &None);
+        for input_split in input_splits {
+            let (key_string, input_mod) = Runtime::split_input(input_split);
+            match key_string.as_str() {
+                "" => (),
+                _ => key_current = NoteName::from_string(key_string),
+            };
+            let chord: Option<Chord> =
+                Chord::from_string(&key_current, input_mod.trim().to_string());
+            chords.push(chord.unwrap());
+        }
+        println!("Enter a layout (Grid, Columns, Stacked):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let layout = ProgressionLayout::from_string(input.trim().to_string());
+        for (index, chord) in chords.iter().enumerate() {
+            let display = &mut self.displays[index];
+            Instrument::show_notes(&mut display.instrument, &chord.notes);
+            display.chord = Some(chord.clone());
+            display.update_voicing();
+            display.render_options = self.display.render_options.clone();
+            // A whole neck per chord won't fit side by side, so each gets the frets its voicing
+            // needs unless a range was chosen.
+            let full = matches!(
+                display.render_options.fret_window,
+                FretWindow::Full | FretWindow::Fit
+            );
+            if layout != ProgressionLayout::Stacked && full {
+                display.render_options.fret_window = display.chord_window();
+            }
+        }
+        for display in self.displays.iter_mut().skip(chords.len()) {
+            display.chord = None;
+            display.voicing = None;
+        }
+        let displays = &self.displays[..chords.len()];
+        if layout == ProgressionLayout::Stacked {
+            for display in displays {
+                println!("{}", display.chord.clone().unwrap());
+                println!("{}", display);
+            }
+            return;
+        }
+        let gap = 4;
+        let width = terminal_width();
+        // Columns share the terminal's width out evenly and cut each neck down to its share.
+        let share = width.map(|width| ((width + gap) / displays.len()).saturating_sub(gap));
+        let blocks: Vec<(String, String)> = displays
+            .iter()
+            .enumerate()
+            .map(|(index, display)| {
+                let chord = display.chord.as_ref().unwrap();
+                let header = format!("{}. {} ({})", index + 1, chord.name, chord.short_name);
+                let options = match (&layout, share, &display.render_options.orientation) {
+                    (ProgressionLayout::Columns, Some(share), Orientation::Horizontal) => {
+                        display.pane_options(share, 0, 0)
+                    }
+                    _ => display.render_options.clone(),
+                };
+                (header, display.render(&options))
+            })
+            .collect();
+        match layout {
+            ProgressionLayout::Columns => println!("{}", join_columns(&blocks, gap)),
+            _ => println!("{}", join_grid(&blocks, width.unwrap_or(80), gap)),
+        }
+    }
+
+    async fn find_chords(&mut self) {
+        let results = Chord::fitting(&self.display.notes);
+        println!("Chords that fit are: ");
+        for result in results {
+            println!("{}", result);
+        }
+    }
+    async fn find_scales(&mut self) {
+        let results = Scale::fitting(&self.display.notes);
+        println!("Scales that fit are:");
+        for result in results {
+            println!("{}", result);
+        }
+    }
+
+    async fn choose_key(&mut self) {
+        println!("Enter a new key (e.g., C, D#, F#):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let key: NoteName = NoteName::from_string(input.trim().to_string());
+        self.display.key = key;
+        println!("Key changed to {}", self.display.key);
+    }
+
+    async fn choose_chord(&mut self) {
+        let mut chord: Option<Chord> = None;
+        loop {
+            println!("Enter a chord (e.g., C major seven, D minor, G six):");
+            let mut input: String = String::new();
+            io::stdin()
+                .read_line(&mut input)
+                .expect("Failed to read input");
+            input = input.to_uppercase();
+            let (key_string, input_mod) = Runtime::split_input(input);
+            match key_string.as_str() {
+                "" => (),
+                _ => self.display.key = NoteName::from_string(key_string),
+            };
+            chord = Chord::from_string(&self.display.key, input_mod.trim().to_string());
+            match chord {
+                None => (),
+                Some(ref _chord) => break,
+            };
+        }
+        self.display.chord = chord.clone();
+        self.display.update_voicing();
+        let chord = chord.unwrap();
+        self.display.notes = chord.notes.clone();
+        self.display.notes.sort();
+        Instrument::show_notes(&mut self.display.instrument, &self.display.notes);
+        println!(
+            "Chord changed to {} {} definition: {}",
+            chord, chord.name, chord.definition
+        );
+    }
+
+    async fn choose_scale(&mut self) {
+        let mut scale: Option<Scale> = None;
+        loop {
+            println!("Enter a scale (e.g., E Major, D Minor, G Dorian):");
+            let mut input: String = String::new();
+            io::stdin()
+                .read_line(&mut input)
+                .expect("Failed to read input");
+            input = input.to_uppercase();
+            let (key_string, input_mod) = Runtime::split_input(input);
+            match key_string.as_str() {
+                "" => (),
+                _ => self.display.key = NoteName::from_string(key_string),
+            };
+            scale = Scale::from_string(&self.display.key, input_mod.trim().to_string());
+            match scale {
+                None => (),
+                Some(ref _scale) => break,
+            };
+        }
+        self.display.scale = scale.clone();
+        let scale = scale.unwrap();
+        self.display.notes = scale.notes.clone();
+        self.display.notes.sort();
+        Instrument::show_notes(&mut self.display.instrument, &self.display.notes);
+        println!(
+            "Scale changed to {} {} definition: {}",
+            scale, scale.name, scale.definition
+        );
+    }
+
+    async fn change_tuning(&mut self) {
+        if !self.display.instrument.has_tuning_types() {
+            println!("This instrument has a custom tuning; only standard guitars and basses can be retuned by type");
+            return;
+        }
+        println!("Enter a tuning (e.g., E Standard, D Drop, G Open):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let (key, input_mod) = Runtime::split_input(input);
+        let tuning_type: TuningType = TuningType::from_string(input_mod.trim().to_string());
+        if tuning_type == TuningType::Custom {
+            println!("Custom tunings are made from the Instruments menu");
+            return;
+        }
+        let key = NoteName::from_string(key);
+        let root_note = NotePitch::from_note_name(&key, 2);
+        self.display.instrument.retune(&tuning_type, &root_note);
+        self.display.update_voicing();
+        self.display.refresh();
+        println!(
+            "Tuning changed to {} {}",
+            self.display.instrument.root_note.note_name, self.display.instrument.tuning_type
+        );
+        println!("{}", self.display.instrument.tension_summary());
+    }
+
+    async fn change_display_options(&mut self) {
+        println!("Enter an orientation (Horizontal, Vertical):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let orientation = Orientation::from_string(input.trim().to_string());
+        println!("Enter a handedness (Right, Left):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let handedness = Handedness::from_string(input.trim().to_string());
+        println!("Enter a string order (Standard, Reversed):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let string_order = StringOrder::from_string(input.trim().to_string());
+        println!("Enter a fret window (Full, Fit, or a range e.g., 5-9):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let fret_window = FretWindow::from_string(input.trim().to_string());
+        println!("Enter a label mode (Names, Degrees, Intervals, Fingerings):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let label_mode = LabelMode::from_string(input.trim().to_string());
+        self.display.render_options = RenderOptions {
+            orientation,
+            handedness,
+            string_order,
+            fret_window,
+            label_mode,
+        };
+        println!("Display changed to {}", self.display.render_options);
+    }
+
+    async fn change_theme(&mut self) {
+        println!("Enter a theme (Dark, Light, Colorblind, Monochrome):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let theme = Theme::from_string(input.trim().to_string());
+        Theme::set(&theme);
+        println!("Theme changed to {}", theme);
+    }
+
+    async fn show_details(&mut self) {
+        println!("Instrument Details:");
+        for line in self.display.details() {
+            println!("\t{}", line);
+        }
+    }
+
+    async fn caged_shapes(&mut self) {
+        if !self.display.instrument.is_standard_guitar_tuning() {
+            println!("CAGED shapes need a six string guitar in standard tuning (any key)");
+            return;
+        }
+        let Some((notes, name, for_scale)) = self.display.chord_or_scale() else {
+            println!("Choose a major or minor chord or scale first");
+            return;
+        };
+        let root = notes[0].clone();
+        let Some(third) = NoteName::third_suffix(&root, &notes) else {
+            println!(
+                "CAGED shapes need either a major or a minor third, {} has neither or both",
+                name
+            );
+            return;
+        };
+        let regions = self.display.instrument.caged_regions(&root, for_scale);
+        println!("Enter a CAGED shape (C, A, G, E, D, All):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let mut options = self.display.render_options.clone();
+        match CagedShape::from_string(input.trim().to_string()) {
+            Some(shape) => {
+                for region in regions.iter().filter(|region| region.shape == shape) {
+                    println!(
+                        "{}: {}{} shape, frets {}-{}",
+                        name, region.shape, third, region.first_fret, region.last_fret
+                    );
+                    options.fret_window = FretWindow::Range(region.first_fret, region.last_fret);
+                    let view = FretboardView::new(&self.display.instrument, &options, &root, None);
+                    println!("{}", view);
+                }
+            }
+            None => {
+                for region in &regions {
+                    println!(
+                        "{}: {}{} shape, frets {}-{}",
+                        name, region.shape, third, region.first_fret, region.last_fret
+                    );
+                }
+                let view = FretboardView::new(&self.display.instrument, &options, &root, None);
+                print!("{}", view);
+                if options.orientation == Orientation::Horizontal {
+                    if options.handedness == Handedness::Right {
+                        print!("  ");
+                    }
+                    for j in options.fret_indices(&self.display.instrument) {
+                        let shapes: String = regions
+                            .iter()
+                            .filter(|region| (region.first_fret..=region.last_fret).contains(&j))
+                            .map(|region| region.shape.to_string())
+                            .collect();
+                        print!("{:<4}", Theme::current().text(shapes));
+                    }
+                    println!();
+                }
+            }
+        }
+    }
+
+    async fn scale_patterns(&mut self) {
+        let Some(scale) = &self.display.scale else {
+            println!("Choose a scale first");
+            return;
+        };
+        println!("Enter a pattern system (3NPS, 2NPS, Octave):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let system = PatternSystem::from_string(input.trim().to_string());
+        if let Some(count) = system.note_count() {
+            if scale.pitch_classes().len() != count {
+                println!("{} patterns need a {} note scale", system, count);
+                return;
+            }
+        }
+        let patterns = self.display.instrument.scale_patterns(scale, &system);
+        if patterns.is_empty() {
+            println!("{} has no {} patterns within the frets", scale.name, system);
+            return;
+        }
+        let numbers: Vec<String> = patterns.iter().map(|p| p.number.to_string()).collect();
+        println!("Enter a pattern number ({}) or All:", numbers.join(", "));
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let chosen: Vec<&ScalePattern> = match input.trim().parse::<usize>() {
+            Ok(number) => patterns.iter().filter(|p| p.number == number).collect(),
+            Err(_) => patterns.iter().collect(),
+        };
+        if chosen.is_empty() {
+            println!("No pattern {}", input.trim());
+        }
+        let mut options = self.display.render_options.clone();
+        let mut instrument = self.display.instrument.clone();
+        for pattern in chosen {
+            let positions: Vec<(usize, usize)> =
+                pattern.notes.iter().map(|&(i, j, _)| (i, j)).collect();
+            instrument.show_positions(&positions);
+            options.fret_window = FretWindow::Range(pattern.first_fret(), pattern.last_fret());
+            print!("{}: {}", scale.name, pattern);
+            let root = &scale.notes[0];
+            let view = FretboardView::new(&instrument, &options, root, Some(pattern.fingering()));
+            println!("{}", view);
+        }
+    }
+
+    async fn find_pitches(&mut self) {
+        println!("Enter a pitch or range of pitches (e.g., E4, E2-G3):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let (low, high) = match input.trim().split_once('-') {
+            Some((low, high)) => (
+                NotePitch::from_string(low.to_string()),
+                NotePitch::from_string(high.to_string()),
+            ),
+            None => {
+                let pitch = NotePitch::from_string(input.trim().to_string());
+                (pitch.clone(), pitch)
+            }
+        };
+        let (low, high) = match low.to_semitones() <= high.to_semitones() {
+            true => (low, high),
+            false => (high, low),
+        };
+        let instrument = &self.display.instrument;
+        match low == high {
+            true => {
+                let positions = instrument.find_pitch(&low);
+                match positions.is_empty() {
+                    true => println!(
+                        "{} is not on this instrument",
+                        Theme::printable(&low.get_name())
+                    ),
+                    false => println!("{} is found at:", Theme::printable(&low.get_name())),
+                }
+                for (i, j) in positions {
+                    println!(
+                        "\tstring {} ({}): fret {}",
+                        instrument.string_count - i,
+                        Theme::printable(&instrument.tuning[i].get_name()),
+                        j
+                    );
+                }
+            }
+            false => println!(
+                "Showing {} to {}",
+                Theme::printable(&low.get_name()),
+                Theme::printable(&high.get_name())
+            ),
+        }
+        self.display.notes = Vec::new();
+        self.display.instrument.show_pitches(&low, &high);
+    }
+
+    async fn highlight_layers(&mut self) {
+        println!("Layers, bottom to top:");
+        for layer in self.display.instrument.all_layers() {
+            println!("\t{}", layer);
+        }
+        println!("Enter a layer to add (Scale, Chord, Voicing, Root, Target), Remove or Clear:");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let instrument = &mut self.display.instrument;
+        match input.trim().to_uppercase().as_str() {
+            "CLEAR" => {
+                instrument.clear_user_layers();
+                match self.display.notes.len() {
+                    0 => instrument.show_all(),
+                    _ => instrument.show_notes(&self.display.notes),
+                }
+                return;
+            }
+            "REMOVE" => {
+                println!("Enter the name of the layer to remove:");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                if !instrument.remove_layer(input.trim()) {
+                    println!("No layer named {}", input.trim());
+                }
+                return;
+            }
+            _ => (),
+        }
+        let source = LayerSource::from_string(input.trim().to_string());
+        let chord = &self.display.chord;
+        let scale = &self.display.scale;
+        let positions = match &source {
+            LayerSource::Scale => scale
+                .as_ref()
+                .map(|scale| instrument.note_positions(&scale.notes)),
+            LayerSource::Chord => chord
+                .as_ref()
+                .map(|chord| instrument.note_positions(&chord.notes)),
+            LayerSource::Voicing => chord
+                .as_ref()
+                .and_then(|chord| instrument.find_voicing(chord))
+                .map(|voicing| {
+                    let fingering = voicing.fingering();
+                    fingering
+                        .positions
+                        .iter()
+                        .map(|&(i, j, _)| (i, j))
+                        .collect()
+                }),
+            LayerSource::Root => {
+                let root = match (chord, scale) {
+                    (Some(chord), _) => chord.notes[0].clone(),
+                    (None, Some(scale)) => scale.notes[0].clone(),
+                    (None, None) => self.display.key.clone(),
+                };
+                Some(instrument.note_positions(&[root]))
+            }
+            LayerSource::Target => {
+                println!("Enter a target pitch (e.g., E4):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let pitch = NotePitch::from_string(input.trim().to_string());
+                Some(instrument.find_pitch(&pitch))
+            }
+        };
+        let Some(positions) = positions else {
+            match source {
+                LayerSource::Voicing => println!("Choose a chord with a playable voicing first"),
+                _ => println!("Choose a {} first", source),
+            }
+            return;
+        };
+        let default_style = source.default_style();
+        println!(
+            "Enter a style (Dim, Normal, Bright, Bold, Blink) or leave blank for {}:",
+            default_style
+        );
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let style = HighlightStyle::from_string(input.trim().to_string()).unwrap_or(default_style);
+        let name = source.to_string();
+        instrument.add_layer(HighlightLayer::new(&name, &style, positions));
+    }
+
+    async fn fret_calculator(&mut self) {
+        println!(
+            "Enter a scale length (e.g., 648, 25.5in, 686-648, 27-25.5in@8) or leave blank for {}:",
+            self.display.instrument.scale_length
+        );
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        if let Some(scale_length) = ScaleLength::from_string(input.trim().to_string()) {
+            self.display.instrument.scale_length = scale_length;
+        }
+        println!("Enter a temperament (Equal, Just, Pythagorean, Meantone):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let temperament = Temperament::from_string(input.trim().to_string());
+        let table = self.display.instrument.fret_table(&temperament);
+        println!("{}", table);
+        println!("Enter a file to export to (.csv or .svg) or leave blank:");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let path = input.trim();
+        let contents = match path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()) {
+            _ if path.is_empty() => return,
+            Some(ext) if ext == "csv" => table.to_csv(),
+            Some(ext) if ext == "svg" => table.to_svg(),
+            _ => {
+                println!("Export files must end in .csv or .svg");
+                return;
+            }
+        };
+        match std::fs::write(path, contents) {
+            Ok(()) => println!("Fret positions written to {}", path),
+            Err(error) => println!("Couldn't write {}: {}", path, error),
+        }
+    }
+
+    async fn string_tension(&mut self) {
+        let instrument = &mut self.display.instrument;
+        let gauges: Vec<String> = instrument
+            .string_gauges
+            .iter()
+            .map(|g| g.to_string())
+            .collect();
+        println!(
+            "Enter {} string gauges from the lowest string, separated by commas, or leave blank for {}:",
+            instrument.string_count,
+            gauges.join(", ")
+        );
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        if !input.trim().is_empty() {
+            let gauges: Vec<StringGauge> = input
+                .split(',')
+                .map(|gauge| StringGauge::from_string(gauge.trim().to_string()))
+                .collect();
+            match gauges.len() == instrument.string_count {
+                true => instrument.string_gauges = gauges,
+                false => println!(
+                    "Expected {} gauges, keeping {}",
+                    instrument.string_count,
+                    instrument.string_gauges.len()
+                ),
+            }
+        }
+        let (slack, tight) = instrument.instrument_type.tension_range();
+        println!(
+            "Scale length {}, {} tuning",
+            instrument.scale_length, instrument.tuning_type
+        );
+        println!(
+            "{:<8}{:<6}{:<7}{:>10}{:>10}",
+            "String", "Note", "Gauge", "lb", "kg"
+        );
+        for (i, ((pitch, gauge), (tension, status))) in instrument
+            .tuning
+            .iter()
+            .zip(&instrument.string_gauges)
+            .zip(instrument.string_tensions())
+            .enumerate()
+        {
+            println!(
+                "{:<8}{:<6}{:<7}{:>10.1}{:>10.1}  {}",
+                instrument.string_count - i,
+                Theme::printable(&pitch.get_name()),
+                gauge,
+                tension,
+                tension * KG_PER_LB,
+                status
+            );
+        }
+        println!(
+            "Enter a target tension in lb to suggest gauges ({:.0}-{:.0} is comfortable), or leave blank:",
+            slack, tight
+        );
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let Ok(target) = input.trim().parse::<f64>() else {
+            return;
+        };
+        for (i, pitch) in instrument.tuning.iter().enumerate() {
+            let length = instrument
+                .scale_length
+                .string_length(i, instrument.string_count);
+            let gauge = StringGauge::suggest(pitch, length, target);
+            println!(
+                "{:<8}{:<6}{:<7}{:>10.1}",
+                instrument.string_count - i,
+                Theme::printable(&pitch.get_name()),
+                gauge,
+                gauge.tension(pitch, length)
+            );
+        }
+    }
+
+    async fn natural_harmonics(&mut self) {
+        println!("Enter a pitch to find as a harmonic (e.g., B4) or leave blank for the map:");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let instrument = &self.display.instrument;
+        let harmonics = match input.trim() {
+            "" => instrument.harmonics(),
+            pitch => {
+                let pitch = NotePitch::from_string(pitch.to_string());
+                let harmonics = instrument.find_harmonic(&pitch);
+                if harmonics.is_empty() {
+                    println!(
+                        "No natural harmonic sounds {}",
+                        Theme::printable(&pitch.get_name())
+                    );
+                    return;
+                }
+                println!(
+                    "{} sounds as a harmonic at:",
+                    Theme::printable(&pitch.get_name())
+                );
+                harmonics
+            }
+        };
+        for (i, open_note) in instrument.tuning.iter().enumerate().rev() {
+            let nodes: Vec<String> = harmonics
+                .iter()
+                .filter(|harmonic| harmonic.string == i)
+                .map(|harmonic| harmonic.to_string())
+                .collect();
+            if !nodes.is_empty() {
+                println!(
+                    "\tstring {} ({}): {}",
+                    instrument.string_count - i,
+                    Theme::printable(&open_note.get_name()),
+                    nodes.join(", ")
+                );
+            }
+        }
+        // Where nodes round to the same fret the lower partial, the easier one to sound, is drawn.
+        let mut labels: Vec<(usize, usize, NotePitch)> = Vec::new();
+        for harmonic in &harmonics {
+            let position = (harmonic.string, harmonic.nearest_fret());
+            if !labels.iter().any(|&(i, j, _)| (i, j) == position) {
+                labels.push((position.0, position.1, harmonic.pitch.clone()));
+            }
+        }
+        let mut overlay = instrument.clone();
+        let positions: Vec<(usize, usize)> = labels.iter().map(|&(i, j, _)| (i, j)).collect();
+        overlay.show_positions(&positions);
+        let options = &self.display.render_options;
+        let view = FretboardView::new(&overlay, options, &self.display.key, None)
+            .with_pitch_labels(labels);
+        println!("{}", view);
+    }
+
+    fn to_session(&self) -> Session {
+        let name = |text: &str| Theme::to_ascii(text);
+        Session {
+            key: self.display.key.clone(),
+            notes: self
+                .display
+                .notes
+                .iter()
+                .map(|note| NoteName::from_number(note.to_number()))
+                .collect(),
+            chord: self.display.chord.as_ref().map(|chord| name(&chord.name)),
+            scale: self.display.scale.as_ref().map(|scale| name(&scale.name)),
+            progression: self
+                .displays
+                .iter()
+                .filter_map(|display| display.chord.as_ref().map(|chord| name(&chord.name)))
+                .collect(),
+            theme: Theme::current(),
+            render_options: self.display.render_options.clone(),
+            instrument: InstrumentDefinition::from_instrument(&self.display.instrument),
+        }
+    }
+
+    fn restore_session(&mut self, session: &Session) -> io::Result<()> {
+        let instrument = session.instrument.to_instrument()?;
+        self.display = DisplayGroup {
+            instrument: instrument.clone(),
+            key: session.key.clone(),
+            notes: session.notes.clone(),
+            chord: session.chord.as_deref().and_then(Chord::from_name),
+            voicing: None,
+            scale: session.scale.as_deref().and_then(Scale::from_name),
+            render_options: session.render_options.clone(),
+            toggled: Vec::new(),
+            suggestions: Suggestions::default(),
+        };
+        self.display.update_voicing();
+        self.display.refresh();
+        // Without colour support the detected monochrome theme stays.
+        if Theme::detect() != Theme::Monochrome {
+            Theme::set(&session.theme);
+        }
+        for (index, display) in self.displays.iter_mut().enumerate() {
+            display.instrument = instrument.clone();
+            display.chord = session
+                .progression
+                .get(index)
+                .and_then(|name| Chord::from_name(name));
+            display.update_voicing();
+            if let Some(chord) = &display.chord {
+                display.instrument.show_notes(&chord.notes);
+            }
+        }
+        Ok(())
+    }
+
+    async fn sessions(&mut self) {
+        println!("Enter Save, Load or List:");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let action = input.trim().to_uppercase();
+        if action == "LIST" || action == "LOAD" {
+            let names = ConfigKind::Session.list();
+            match names.is_empty() {
+                true => println!("No saved sessions"),
+                false => println!("Saved sessions: {}", names.join(", ")),
+            }
+        }
+        match action.as_str() {
+            "SAVE" => {
+                println!("Enter a name for the session:");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                match ConfigKind::Session.save(input.trim(), &self.to_session()) {
+                    Ok(path) => println!("Session saved to {}", path.display()),
+                    Err(error) => println!("Couldn't save session: {}", error),
+                }
+            }
+            "LOAD" => {
+                println!("Enter the session to load:");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let loaded = ConfigKind::Session
+                    .load::<Session>(input.trim())
+                    .and_then(|session| self.restore_session(&session));
+                match loaded {
+                    Ok(()) => println!("Session {} loaded", input.trim()),
+                    Err(error) => println!("Couldn't load session: {}", error),
+                }
+            }
+            "LIST" => (),
+            _ => println!("Invalid choice, please try again."),
+        }
+    }
+
+    async fn instruments(&mut self) {
+        println!("Enter Save, Load, Custom or List (save one as default to start with it):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let action = input.trim().to_uppercase();
+        if action == "LIST" || action == "LOAD" {
+            let names = ConfigKind::Instrument.list();
+            match names.is_empty() {
+                true => println!("No saved instruments"),
+                false => println!("Saved instruments: {}", names.join(", ")),
+            }
+        }
+        let instrument = match action.as_str() {
+            "SAVE" => None,
+            "LOAD" => {
+                println!("Enter the instrument to load:");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let loaded = ConfigKind::Instrument
+                    .load::<InstrumentDefinition>(input.trim())
+                    .and_then(|definition| definition.to_instrument());
+                match loaded {
+                    Ok(instrument) => Some(instrument),
+                    Err(error) => {
+                        println!("Couldn't load instrument: {}", error);
+                        return;
+                    }
+                }
+            }
+            "CUSTOM" => {
+                println!("Enter an instrument (Guitar, Bass, Mandolin, Banjo, Ukulele):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let instrument_type = InstrumentType::from_string(input.trim().to_string());
+                println!("Enter the open strings from the lowest, separated by commas (e.g., D2, A2, D3, G3, B3, E4):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let tuning: Vec<NotePitch> = input
+                    .split(',')
+                    .map(|pitch| NotePitch::from_string(pitch.trim().to_string()))
+                    .collect();
+                println!("Enter the number of frets:");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let Ok(fret_count) = input.trim().parse::<usize>() else {
+                    println!("{} is not a number of frets", input.trim());
+                    return;
+                };
+                if let Err(error) = Instrument::check_size(&tuning, fret_count) {
+                    println!("Couldn't make the instrument: {}", error);
+                    return;
+                }
+                Some(Instrument::from_tuning(
+                    &instrument_type,
+                    &TuningType::Custom,
+                    &tuning,
+                    fret_count,
+                ))
+            }
+            "LIST" => return,
+            _ => {
+                println!("Invalid choice, please try again.");
+                return;
+            }
+        };
+        if let Some(instrument) = instrument {
+            self.display.instrument = instrument;
+            self.display.update_voicing();
+            self.display.refresh();
+            if action == "LOAD" {
+                return;
+            }
+        }
+        println!("Enter a name to save the instrument as, or leave blank:");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        if input.trim().is_empty() {
+            return;
+        }
+        let definition = InstrumentDefinition::from_instrument(&self.display.instrument);
+        match ConfigKind::Instrument.save(input.trim(), &definition) {
+            Ok(path) => println!("Instrument saved to {}", path.display()),
+            Err(error) => println!("Couldn't save instrument: {}", error),
+        }
+    }
+
+    // Every instrument is drawn over the same frets so the same positions line up: the chosen
+    // range, or the first octave when the window is the full neck or fits the highlights.
+    async fn compare_instruments(&mut self) {
+        let Some((notes, name, _for_scale)) = self.display.chord_or_scale() else {
+            println!("Choose a chord or scale first");
+            return;
+        };
+        let saved = ConfigKind::Instrument.list();
+        println!("Enter instruments separated by commas: Current, Guitar, Bass, Mandolin, Banjo, Ukulele or a saved instrument (blank for Guitar, Bass, Mandolin, Ukulele):");
+        if !saved.is_empty() {
+            println!("Saved instruments: {}", saved.join(", "));
+        }
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let input = match input.trim() {
+            "" => "Guitar, Bass, Mandolin, Ukulele",
+            names => names,
+        };
+        let mut instruments: Vec<(String, Instrument)> = Vec::new();
+        for entry in input.split(',').map(|entry| entry.trim()) {
+            let instrument = if saved.iter().any(|name| name == entry) {
+                let loaded = ConfigKind::Instrument
+                    .load::<InstrumentDefinition>(entry)
+                    .and_then(|definition| definition.to_instrument());
+                match loaded {
+                    Ok(instrument) => instrument,
+                    Err(error) => {
+                        println!("Couldn't load instrument {}: {}", entry, error);
+                        continue;
+                    }
+                }
+            } else if entry.eq_ignore_ascii_case("CURRENT") {
+                self.display.instrument.clone()
+            } else {
+                match InstrumentType::parse(entry) {
+                    Some(instrument_type) => Instrument::standard(&instrument_type),
+                    None => {
+                        println!("Unknown instrument {}, skipping", entry);
+                        continue;
+                    }
+                }
+            };
+            let label = match saved.iter().any(|name| name == entry) {
+                true => format!("{}: {}", entry, instrument.describe()),
+                false => instrument.describe(),
+            };
+            instruments.push((label, instrument));
+        }
+        if instruments.is_empty() {
+            return;
+        }
+        println!("Enter a layout (Stacked, Side):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let layout = CompareLayout::from_string(input.trim().to_string());
+        let mut options = self.display.render_options.clone();
+        if let FretWindow::Full | FretWindow::Fit = options.fret_window {
+            options.fret_window = FretWindow::Range(0, 12);
+        }
+        let root = &notes[0];
+        let gap = 4;
+        // Side by side, the instruments share the terminal's width like fret_ninja's columns.
+        let share = match (&layout, &options.orientation) {
+            (CompareLayout::SideBySide, Orientation::Horizontal) => terminal_width()
+                .map(|width| ((width + gap) / instruments.len()).saturating_sub(gap)),
+            _ => None,
+        };
+        println!("{} on {}, {}", name, options.fret_window, layout);
+        let blocks: Vec<(String, String)> = instruments
+            .into_iter()
+            .map(|(label, mut instrument)| {
+                instrument.show_notes(&notes);
+                let (label, options) = match share {
+                    Some(share) => (
+                        label.chars().take(share).collect(),
+                        options.pane(&instrument, share, 0, 0),
+                    ),
+                    None => (label, options.clone()),
+                };
+                let view = FretboardView::new(&instrument, &options, root, None);
+                (label, view.to_string())
+            })
+            .collect();
+        match layout {
+            CompareLayout::Stacked => {
+                for (label, view) in blocks {
+                    println!("\n{}\n{}", label, view);
+                }
+            }
+            CompareLayout::SideBySide => println!("\n{}", join_columns(&blocks, gap)),
+        }
+    }
+
+    async fn export_svg(&mut self) {
+        println!("Enter what to export (Current, Fret Ninja, Patterns):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let choice = input.trim().to_uppercase();
+        if choice == "FRET NINJA" && self.displays.iter().all(|d| d.chord.is_none()) {
+            println!("Play Fret Ninja first");
+            return;
+        }
+        let scale = match (choice.as_str(), &self.display.scale) {
+            ("CURRENT" | "FRET NINJA", _) => None,
+            ("PATTERNS", Some(scale)) => Some(scale.clone()),
+            ("PATTERNS", None) => {
+                println!("Choose a scale first");
+                return;
+            }
+            _ => {
+                println!("Invalid choice, please try again.");
+                return;
+            }
+        };
+        let system = match scale {
+            Some(_) => {
+                println!("Enter a pattern system (3NPS, 2NPS, Octave):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let system = PatternSystem::from_string(input.trim().to_string());
+                if let (Some(count), Some(scale)) = (system.note_count(), &scale) {
+                    if scale.pitch_classes().len() != count {
+                        println!("{} patterns need a {} note scale", system, count);
+                        return;
+                    }
+                }
+                Some(system)
+            }
+            None => None,
+        };
+        println!(
+            "Enter a cell size in pixels (e.g., 40x30, blank for {}):",
+            SvgSize::new()
+        );
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let size = SvgSize::from_string(input.trim().to_string());
+        let diagrams: Vec<SvgDiagram> = match (&scale, &system) {
+            (Some(scale), Some(system)) => {
+                let mut options = self.display.render_options.clone();
+                let mut instrument = self.display.instrument.clone();
+                let mut diagrams = Vec::new();
+                for pattern in self.display.instrument.scale_patterns(scale, system) {
+                    let positions: Vec<(usize, usize)> =
+                        pattern.notes.iter().map(|&(i, j, _)| (i, j)).collect();
+                    instrument.show_positions(&positions);
+                    options.fret_window =
+                        FretWindow::Range(pattern.first_fret(), pattern.last_fret());
+                    let title = format!("{} {} pattern {}", scale.name, system, pattern.number);
+                    let root = &scale.notes[0];
+                    let view =
+                        FretboardView::new(&instrument, &options, root, Some(pattern.fingering()));
+                    diagrams.push(view.to_svg(&title, &size));
+                }
+                diagrams
+            }
+            _ if choice == "FRET NINJA" => self
+                .displays
+                .iter()
+                .filter(|display| display.chord.is_some())
+                .map(|display| display.to_svg(&size))
+                .collect(),
+            _ => vec![self.display.to_svg(&size)],
+        };
+        if diagrams.is_empty() {
+            println!("Nothing to export");
+            return;
+        }
+        println!("Enter a file to write (e.g., handout.svg):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let path = input.trim();
+        if path.is_empty() {
+            return;
+        }
+        match std::fs::write(path, SvgDiagram::document(&diagrams, &size)) {
+            Ok(()) => println!("{} diagram(s) written to {}", diagrams.len(), path),
+            Err(error) => println!("Couldn't write {}: {}", path, error),
+        }
+    }
+
+    // Writes the Fret Ninja progression as an HTML page ready to print.
+    async fn export_chord_sheet(&mut self) {
+        let displays: Vec<&DisplayGroup> = self
+            .displays
+            .iter()
+            .filter(|display| display.chord.is_some())
+            .collect();
+        if displays.is_empty() {
+            println!("Play Fret Ninja first");
+            return;
+        }
+        let names: Vec<String> = displays
+            .iter()
+            .filter_map(|display| display.chord.as_ref())
+            .map(|chord| chord.short_name.clone())
+            .collect();
+        println!("Enter a title (blank for {}):", names.join(" - "));
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let title = match input.trim() {
+            "" => names.join(" - "),
+            title => title.to_string(),
+        };
+        println!("Enter a paper size (A4, Letter):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let paper = PaperSize::from_string(input.trim().to_string());
+        println!("Enter a file to write (e.g., sheet.html):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let path = input.trim();
+        if path.is_empty() {
+            return;
+        }
+        let sheet = ChordSheet {
+            title,
+            paper,
+            displays,
+        };
+        match std::fs::write(path, sheet.to_html()) {
+            Ok(()) => println!("Chord sheet written to {}", path),
+            Err(error) => println!("Couldn't write {}: {}", path, error),
+        }
+    }
+
+    fn read_direction() -> RunDirection {
+        println!("Enter a direction (Up, Down, Both):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        RunDirection::from_string(input.trim().to_string())
+    }
+
+    async fn export_tab(&mut self) {
+        println!("Enter a source (Scale, Arpeggio, Chord, Progression):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let instrument = &self.display.instrument;
+        let mut tab = Tab::new(&instrument.tuning);
+        match input.trim().to_uppercase().as_str() {
+            "SCALE" => {
+                let Some(scale) = &self.display.scale else {
+                    println!("Choose a scale first");
+                    return;
+                };
+                println!("Enter a pattern system (3NPS, 2NPS, Octave):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let system = PatternSystem::from_string(input.trim().to_string());
+                let patterns = instrument.scale_patterns(scale, &system);
+                if patterns.is_empty() {
+                    println!("{} has no {} patterns within the frets", scale.name, system);
+                    return;
+                }
+                let numbers: Vec<String> = patterns.iter().map(|p| p.number.to_string()).collect();
+                println!("Enter a pattern number ({}):", numbers.join(", "));
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let number = input.trim().parse::<usize>().unwrap_or(1);
+                let Some(pattern) = patterns.iter().find(|p| p.number == number) else {
+                    println!("No pattern {}", input.trim());
+                    return;
+                };
+                let notes: Vec<(usize, usize)> =
+                    pattern.notes.iter().map(|&(i, j, _)| (i, j)).collect();
+                tab.push_run(&notes, &Runtime::read_direction());
+            }
+            "ARPEGGIO" | "CHORD" => {
+                let Some(chord) = &self.display.chord else {
+                    println!("Choose a chord first");
+                    return;
+                };
+                let Some(voicing) = instrument.find_voicing(chord) else {
+                    println!("No voicing of {} within reach", chord.short_name);
+                    return;
+                };
+                match input.trim().to_uppercase().as_str() {
+                    "ARPEGGIO" => {
+                        let notes = instrument.arpeggio(chord, &voicing);
+                        tab.push_run(&notes, &Runtime::read_direction());
+                    }
+                    _ => tab.push_chord(&voicing.frets),
+                }
+            }
+            "PROGRESSION" => {
+                let chords: Vec<&Chord> = self
+                    .displays
+                    .iter()
+                    .filter_map(|display| display.chord.as_ref())
+                    .collect();
+                if chords.is_empty() {
+                    println!("Play Fret Ninja first");
+                    return;
+                }
+                for chord in chords {
+                    match instrument.find_voicing(chord) {
+                        Some(voicing) => {
+                            tab.push_chord(&voicing.frets);
+                            tab.push_bar();
+                        }
+                        None => println!("No voicing of {} within reach", chord.short_name),
+                    }
+                }
+            }
+            _ => {
+                println!("Invalid choice, please try again.");
+                return;
+            }
+        }
+        let default_width = terminal_width().unwrap_or(80);
+        println!("Enter a line width (blank for {}):", default_width);
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let width = input.trim().parse::<usize>().unwrap_or(default_width);
+        let text = tab.render(width);
+        println!("Enter a file to write, or leave blank to print:");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let path = input.trim();
+        if path.is_empty() {
+            println!("\n{}", text);
+            return;
+        }
+        match std::fs::write(path, text) {
+            Ok(()) => println!("Tab written to {}", path),
+            Err(error) => println!("Couldn't write {}: {}", path, error),
         }
     }
-}
-
-struct Runtime {
-    displays: Vec<DisplayGroup>,
-    display: DisplayGroup,
-    audio_engine: AudioEngine,
-}
 
-impl Runtime {
-    fn new() -> Self {
-        let mut displays: Vec<DisplayGroup> = Vec::new();
-        for _i in 0..=3 {
-            let display = DisplayGroup::new();
-            displays.push(display);
+    async fn export_audio(&mut self) {
+        println!("Enter what to render (Note, Chord, Scale, Progression):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let instrument = &self.display.instrument;
+        let sounds: Vec<Vec<NotePitch>> = match input.trim().to_uppercase().as_str() {
+            "NOTE" => {
+                println!("Enter a pitch (e.g., E2, F#4):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                vec![vec![NotePitch::from_string(input.trim().to_string())]]
+            }
+            "CHORD" => match &self.display.chord {
+                Some(chord) => vec![instrument.chord_pitches(chord)],
+                None => {
+                    println!("Choose a chord first");
+                    return;
+                }
+            },
+            "SCALE" => match &self.display.scale {
+                Some(scale) => NotePitch::stack(&scale.notes, 3)
+                    .into_iter()
+                    .map(|pitch| vec![pitch])
+                    .collect(),
+                None => {
+                    println!("Choose a scale first");
+                    return;
+                }
+            },
+            "PROGRESSION" => {
+                let chords: Vec<&Chord> = self
+                    .displays
+                    .iter()
+                    .filter_map(|display| display.chord.as_ref())
+                    .collect();
+                if chords.is_empty() {
+                    println!("Play Fret Ninja first");
+                    return;
+                }
+                chords
+                    .into_iter()
+                    .map(|chord| instrument.chord_pitches(chord))
+                    .collect()
+            }
+            _ => {
+                println!("Invalid choice, please try again.");
+                return;
+            }
+        };
+        println!("Enter a note length in seconds (blank for 1):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let length = match input.trim().parse::<f32>() {
+            Ok(length) if length > 0.0 => length,
+            _ => 1.0,
+        };
+        println!("Enter a file to write (e.g., chord.wav):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let path = input.trim();
+        if path.is_empty() {
+            println!("No file given, nothing written");
+            return;
         }
-        let display = DisplayGroup::new();
-        let audio_engine: AudioEngine = AudioEngine::new();
-        Runtime {
-            displays,
-            display,
-            audio_engine,
+        let events: Vec<AudioEvent> = sounds
+            .iter()
+            .map(|pitches| AudioEvent::strum(pitches, STRUM_SPREAD_SECS, length))
+            .collect();
+        let samples = self.audio_engine.render(&events);
+        match self.audio_engine.write_wav(path, &samples) {
+            Ok(()) => println!(
+                "{:.1} seconds of audio written to {}",
+                self.audio_engine.samples_to_secs(samples.len()),
+                path
+            ),
+            Err(error) => println!("Couldn't write {}: {}", path, error),
         }
     }
 
-    async fn start(&mut self) {
-        self.show_details().await;
-        loop {
-            self.show_notes_displayed();
-            println!("\n{}", self.display.instrument);
-            println!("\nMenu:");
-            println!("1 - Fret Ninja");
-            println!("2 - Choose Key");
-            println!("3 - Choose Chord");
-            println!("4 - Choose Scale");
-            println!("5 - Add Note(s)");
-            println!("6 - Remove Note(s)");
-            println!("7 - Find Scales");
-            println!("8 - Find Chords");
-            println!("9 - Display Full Instrument");
-            println!("10 - Show Details");
-            println!("11 - Change Instrument Tuning");
-            println!("0 - Exit");
-            println!("Enter your choice:");
-
+    async fn sound_settings(&mut self) {
+        let settings = &mut self.audio_engine.settings;
+        println!("Voice: {}", settings.model);
+        println!("Waveform: {}", settings.waveform);
+        println!("Envelope: {}", settings.envelope);
+        println!("Gain: {}", settings.gain);
+        println!("Enter a waveform (Sine, Triangle, Saw, Square, blank to keep):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        if !input.trim().is_empty() {
+            settings.waveform = Waveform::from_string(input.trim().to_string());
+        }
+        println!("Enter a voice (Oscillator, Plucked, blank to keep):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        if !input.trim().is_empty() {
+            settings.model = VoiceModel::from_string(input.trim().to_string());
+        }
+        if let VoiceModel::PluckedString {
+            damping,
+            brightness,
+        } = &mut settings.model
+        {
+            println!("Enter damping and brightness from 0 to 1 (e.g., 0.2, 0.5, blank to keep):");
             let mut input: String = String::new();
             io::stdin()
                 .read_line(&mut input)
                 .expect("Failed to read input");
-
-            match input.trim() {
-                "1" => self.fret_ninja().await,
-                "2" => self.choose_key().await,
-                "3" => self.choose_chord().await,
-                "4" => self.choose_scale().await,
-                "5" => self.add_notes().await,
-                "6" => self.remove_notes().await,
-                "7" => self.find_scales().await,
-                "8" => self.find_chords().await,
-                "9" => self.display_full_instrument().await,
-                "10" => self.show_details().await,
-                "11" => self.change_tuning().await,
-                "0" => {
-                    println!("Exiting...");
-                    break;
+            let values: Vec<Option<f32>> = input
+                .split(',')
+                .map(|value| value.trim().parse::<f32>().ok())
+                .collect();
+            match values[..] {
+                [Some(new_damping @ 0.0..=1.0), Some(new_brightness @ 0.0..=1.0)] => {
+                    *damping = new_damping;
+                    *brightness = new_brightness;
                 }
-                _ => println!("Invalid choice, please try again."),
+                _ if input.trim().is_empty() => (),
+                _ => println!("Keeping damping {} and brightness {}", damping, brightness),
             }
         }
-    }
-
-    async fn remove_notes(&mut self) {
-        println!("Enter Notes you wish to remove seperated by commas");
+        println!(
+            "Enter attack, decay, sustain and release (e.g., 0.01, 0.1, 0.7, 0.2, blank to keep):"
+        );
         let mut input: String = String::new();
         io::stdin()
             .read_line(&mut input)
             .expect("Failed to read input");
-        let note_strs: Vec<&str> = input.split(',').collect();
-        for note_str in note_strs {
-            if let Some(index) = self
-                .display
-                .notes
-                .iter()
-                .position(|x| x == &NoteName::from_string(note_str.trim().to_string()))
-            {
-                self.display.notes.remove(index);
-            } else {
-                println!(
-                    "{} not in notes displayed",
-                    NoteName::from_string(note_str.trim().to_string())
-                )
-            }
+        if !input.trim().is_empty() {
+            settings.envelope = Envelope::from_string(input.trim().to_string());
+        }
+        println!("Enter a gain from 0 to 1 (blank to keep):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        match input.trim().parse::<f32>() {
+            Ok(gain) if (0.0..=1.0).contains(&gain) => settings.gain = gain,
+            _ if input.trim().is_empty() => (),
+            _ => println!("Gain must be from 0 to 1, keeping {}", settings.gain),
+        }
+        println!("Play a test chord? (Y/N)");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        if input.trim().eq_ignore_ascii_case("Y") {
+            let chord = Chord::from_number(&self.display.key, 0);
+            let pitches = self.display.instrument.chord_pitches(&chord);
+            let events = vec![AudioEvent::strum(&pitches, STRUM_SPREAD_SECS, 1.0)];
+            self.audio_engine.play_events(events).await;
         }
-        self.display.instrument.show_notes(&self.display.notes);
     }
 
-    fn show_notes_displayed(&mut self) {
-        match self.display.notes.len() {
-            0 => (),
-            _ => {
-                print!("The notes you have selected are: ");
-                for note in &self.display.notes {
-                    print!("{} ", note);
+    // Sounds the current chord as voiced on the neck. Block chords and strums last a bar of four
+    // beats and arpeggios take a beat per tone.
+    async fn play_chord(&mut self) {
+        let Some(chord) = &self.display.chord else {
+            println!("Choose a chord first");
+            return;
+        };
+        let pitches = self.display.instrument.chord_pitches(chord);
+        println!("Enter a mode (Block, Down, Up, Arpeggio):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let playback = ChordPlayback::from_string(input.trim().to_string());
+        let spread_secs = match playback {
+            ChordPlayback::StrumDown | ChordPlayback::StrumUp => {
+                println!(
+                    "Enter the time between strings in seconds (blank for {}):",
+                    STRUM_SPREAD_SECS
+                );
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                match input.trim().parse::<f32>() {
+                    Ok(spread) if spread >= 0.0 => spread,
+                    _ => STRUM_SPREAD_SECS,
                 }
-                print!("\n");
             }
+            _ => 0.0,
+        };
+        let pattern = match playback {
+            ChordPlayback::Arpeggio => {
+                println!("Enter a pattern (Up, Down, Both, or tones e.g., 1 3 2 4):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                Some(ArpeggioPattern::from_string(input.trim().to_string()))
+            }
+            _ => None,
+        };
+        println!("Enter a tempo in beats per minute (blank for 120):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let beat_secs = match input.trim().parse::<f32>() {
+            Ok(tempo) if tempo > 0.0 => 60.0 / tempo,
+            _ => 0.5,
+        };
+        let mut low_to_high = pitches.clone();
+        low_to_high.sort_by_key(|pitch| pitch.to_semitones());
+        let mut upstroke = pitches.clone();
+        upstroke.reverse();
+        let events = match (&playback, &pattern) {
+            (ChordPlayback::Arpeggio, Some(pattern)) => pattern.events(&low_to_high, beat_secs),
+            (ChordPlayback::StrumDown, _) => {
+                vec![AudioEvent::strum(&pitches, spread_secs, beat_secs * 4.0)]
+            }
+            (ChordPlayback::StrumUp, _) => {
+                vec![AudioEvent::strum(&upstroke, spread_secs, beat_secs * 4.0)]
+            }
+            _ => vec![AudioEvent::new(&pitches, beat_secs * 4.0)],
+        };
+        let names: Vec<String> = pitches
+            .iter()
+            .map(|pitch| Theme::printable(&pitch.get_name()))
+            .collect();
+        match &pattern {
+            Some(pattern) => println!("Playing {} ({}) {}", chord.name, names.join(" "), pattern),
+            None => println!("Playing {} ({}) {}", chord.name, names.join(" "), playback),
         }
+        self.audio_engine.play_events(events).await;
     }
 
-    async fn add_notes(&mut self) {
-        println!("Enter Notes you wish to add seperated by commas");
+    // Sounds the current scale a beat per note, optionally redrawing the neck on each beat with
+    // the sounding note picked out from the rest of the scale.
+    async fn play_scale(&mut self) {
+        let Some(scale) = &self.display.scale else {
+            println!("Choose a scale first");
+            return;
+        };
+        let direction = Runtime::read_direction();
+        println!("Enter a sequence (Straight, Thirds, Fourths, Fifths, Sixths, Groups of 3):");
         let mut input: String = String::new();
         io::stdin()
             .read_line(&mut input)
             .expect("Failed to read input");
-        let note_strs: Vec<&str> = input.split(',').collect();
-        let mut notes: Vec<NoteName> = Vec::new();
-        for note_str in note_strs {
-            notes.push(NoteName::from_string(note_str.trim().to_string()));
-        }
-        for note in &notes {
-            self.display.notes.push(note.clone());
-        }
-        self.display.notes.sort();
-        self.display.instrument.show_notes(&self.display.notes);
+        let sequence = ScaleSequence::from_string(input.trim().to_string());
+        let default_start = NotePitch::from_note_name(&scale.notes[0], 3);
+        println!(
+            "Enter a starting pitch (blank for {}):",
+            Theme::printable(&default_start.get_name())
+        );
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let start = match input.trim() {
+            "" => default_start,
+            pitch => NotePitch::from_string(pitch.to_string()),
+        };
+        println!("Enter a number of octaves (blank for 1):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let octaves = input.trim().parse::<usize>().unwrap_or(1).clamp(1, 4);
+        println!("Enter a tempo in beats per minute (blank for 120):");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let beat_secs = match input.trim().parse::<f32>() {
+            Ok(tempo) if tempo > 0.0 => 60.0 / tempo,
+            _ => 0.5,
+        };
+        println!("Highlight the sounding note on the fretboard? (Y/N)");
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read input");
+        let highlight = input.trim().eq_ignore_ascii_case("Y");
+        let notes = sequence.order(&scale.run(&start, octaves), &direction);
+        let events: Vec<AudioEvent> = notes
+            .iter()
+            .map(|pitch| AudioEvent::new(std::slice::from_ref(pitch), beat_secs))
+            .collect();
+        println!("Playing {} {}, {}", scale.name, sequence, direction);
+        let display = &self.display;
+        let follow = async {
+            let mut instrument = display.instrument.clone();
+            instrument.layers =
+                vec![instrument.notes_layer("scale", &HighlightStyle::Dim, &scale.notes)];
+            let redraw = highlight && io::stdout().is_terminal();
+            let mut drawn_lines = 0;
+            for pitch in &notes {
+                let name = Theme::printable(&pitch.get_name());
+                match (highlight, redraw) {
+                    (true, true) => {
+                        let layer = HighlightLayer::new(
+                            "playing",
+                            &HighlightStyle::Bold,
+                            instrument.find_pitch(pitch),
+                        );
+                        instrument.add_layer(layer);
+                        let options = &display.render_options;
+                        let view = FretboardView::new(&instrument, options, &scale.notes[0], None);
+                        let frame = format!("{}\n{}", name, view);
+                        // Back up over the last frame so the neck stays in place.
+                        if drawn_lines > 0 {
+                            let _ = crossterm::execute!(
+                                io::stdout(),
+                                crossterm::cursor::MoveUp(drawn_lines),
+                                crossterm::terminal::Clear(
+                                    crossterm::terminal::ClearType::FromCursorDown
+                                )
+                            );
+                        }
+                        println!("{}", frame);
+                        drawn_lines = frame.lines().count() as u16;
+                    }
+                    _ => println!("{}", name),
+                }
+                tokio::time::sleep(Duration::from_secs_f32(beat_secs)).await;
+            }
+        };
+        tokio::join!(self.audio_engine.play_events(events), follow);
     }
 
-    async fn fret_ninja(&mut self) {
-        println!("Choose Chord progession seperated by commas");
+    // Starts and stops the click or changes it. Changes take effect straight away while it's
+    // running, and it keeps going while the rest of the menu is used.
+    async fn metronome(&mut self) {
+        println!("Metronome: {}", self.metronome);
+        println!("Enter Start, Stop, Tempo, Time, Accents, Subdivisions or Tap:");
         let mut input: String = String::new();
         io::stdin()
             .read_line(&mut input)
             .expect("Failed to read input");
-        let mut chords = Vec::new();
-        let input_splits = Runtime::split_input_advanced(input);
-        let mut key_current = NoteName::new(&NaturalNote::C, &None);
-        for input_split in input_splits {
-            let (key_string, input_mod) = Runtime::split_input(input_split);
-            match key_string.as_str() {
-                "" => (),
-                _ => key_current = NoteName::from_string(key_string),
-            };
-            let chord: Option<Chord> =
-                Chord::from_string(&key_current, input_mod.trim().to_string());
-            chords.push(chord.unwrap());
-        }
-        for (index, chord) in chords.iter().enumerate() {
-            Instrument::show_notes(&mut self.displays[index].instrument, &chord.notes);
-            self.displays[index].chord = Some(chord.clone());
+        let metronome = &mut self.metronome;
+        match input.trim().to_uppercase().as_str() {
+            "START" => {
+                metronome.start(&self.audio_engine);
+                println!("Metronome: {}", metronome);
+                return;
+            }
+            "STOP" => {
+                metronome.stop();
+                println!("Metronome stopped");
+                return;
+            }
+            "TEMPO" => {
+                println!("Enter a tempo in beats per minute (20-300):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                match input.trim().parse::<f32>() {
+                    Ok(bpm) if (20.0..=300.0).contains(&bpm) => metronome.bpm = bpm,
+                    _ => println!("Keeping {} BPM", metronome.bpm),
+                }
+            }
+            "TIME" => {
+                println!("Enter a time signature (e.g., 4/4, 3/4, 6/8):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                metronome.time_signature = TimeSignature::from_string(input.trim().to_string());
+                metronome.accents = Accent::default_pattern(metronome.time_signature.beats);
+            }
+            "ACCENTS" => {
+                println!(
+                    "Enter {} beats of X (accent), x (beat) or . (silent), e.g., Xxxx:",
+                    metronome.time_signature.beats
+                );
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                let accents: Option<Vec<Accent>> = input
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(Accent::parse)
+                    .collect();
+                match accents {
+                    Some(accents) if accents.len() == metronome.time_signature.beats => {
+                        metronome.accents = accents
+                    }
+                    _ => println!("Accents need one X, x or . per beat, keeping them"),
+                }
+            }
+            "SUBDIVISIONS" => {
+                println!("Enter clicks per beat (1-4):");
+                let mut input: String = String::new();
+                io::stdin()
+                    .read_line(&mut input)
+                    .expect("Failed to read input");
+                match input.trim().parse::<usize>() {
+                    Ok(subdivisions @ 1..=4) => metronome.subdivisions = subdivisions,
+                    _ => println!("Keeping {} per beat", metronome.subdivisions),
+                }
+            }
+            "TAP" => {
+                println!("Press Enter on each beat, then type anything and Enter to finish:");
+                let mut taps: Vec<Instant> = Vec::new();
+                loop {
+                    let mut input: String = String::new();
+                    let read = io::stdin()
+                        .read_line(&mut input)
+                        .expect("Failed to read input");
+                    if read == 0 || !input.trim().is_empty() {
+                        break;
+                    }
+                    taps.push(Instant::now());
+                    if let Some(bpm) = Metronome::tap_tempo(&taps) {
+                        println!("{} BPM", bpm);
+                    }
+                }
+                match Metronome::tap_tempo(&taps) {
+                    Some(bpm) => metronome.bpm = bpm,
+                    None => println!("Tap at least twice, keeping {} BPM", metronome.bpm),
+                }
+            }
+            _ => {
+                println!("Invalid choice, please try again.");
+                return;
+            }
         }
-        for display in &self.displays {
-            println!("{}", display.chord.clone().unwrap());
-            println!("{}", display.instrument);
+        if metronome.is_running() {
+            metronome.start(&self.audio_engine);
         }
+        println!("Metronome: {}", metronome);
     }
 
-    async fn find_chords(&mut self) {
-        let mut results: Vec<Chord> = Vec::new();
-        for i in 0..=11 {
-            let key = NoteName::from_number(i);
-            for j in 0..=23 {
-                let chord = Chord::from_number(&key, j);
-                let mut works = true;
-                let mut contains: Vec<bool> = Vec::new();
-                for _note in &chord.notes {
-                    contains.push(false);
-                }
-                for (k, note) in chord.notes.iter().enumerate() {
-                    for display_note in &self.display.notes {
-                        if note == display_note {
-                            contains[k] = true;
-                        }
-                    }
-                }
-                for value in contains {
-                    if value == false {
-                        works = false
+    // A full screen view that redraws the neck as soon as a key is pressed, with the instrument
+    // details alongside when the terminal is wide enough.
+    async fn interactive(&mut self) {
+        if !io::stdout().is_terminal() {
+            println!("Interactive mode needs a terminal");
+            return;
+        }
+        let mut state = InteractiveState {
+            input: None,
+            status: "Press : to type a command (key, chord, scale, notes, clear)".to_string(),
+            fret_offset: 0,
+            cursor: None,
+        };
+        if let Err(error) = self.run_interactive(&mut state) {
+            println!("Interactive mode stopped: {}", error);
+        }
+    }
+
+    fn run_interactive(&mut self, state: &mut InteractiveState) -> io::Result<()> {
+        let _guard = TerminalGuard::enter()?;
+        loop {
+            self.display.update_suggestions();
+            self.draw_interactive(state)?;
+            match crossterm::event::read()? {
+                crossterm::event::Event::Key(key)
+                    if key.kind == crossterm::event::KeyEventKind::Press =>
+                {
+                    let keep_going = self.interactive_key(key, state);
+                    if !keep_going {
+                        return Ok(());
                     }
                 }
-                if works == true {
-                    results.push(chord.clone());
+                crossterm::event::Event::Mouse(mouse)
+                    if mouse.kind
+                        == crossterm::event::MouseEventKind::Down(
+                            crossterm::event::MouseButton::Left,
+                        ) =>
+                {
+                    self.interactive_click(mouse.column as usize, mouse.row as usize, state)?;
                 }
+                _ => (),
             }
         }
-        println!("Chords that fit are: ");
-        for result in results {
-            println!("{}", result);
-        }
     }
-    async fn find_scales(&mut self) {
-        let mut results: Vec<Scale> = Vec::new();
-        for i in 0..=11 {
-            let key = NoteName::from_number(i);
-            for j in 0..=16 {
-                let scale = Scale::from_number(&key, j);
-                let mut works = true;
-                let mut contains: Vec<bool> = Vec::new();
-                for _note in &self.display.notes {
-                    contains.push(false);
-                }
-                for (k, note) in self.display.notes.iter().enumerate() {
-                    for scale_note in &scale.notes {
-                        if note == scale_note {
-                            contains[k] = true;
+
+    // The neck pane's options, width and height for the terminal's size. The pane starts on the
+    // third row, under the title.
+    fn interactive_layout(
+        &self,
+        state: &InteractiveState,
+    ) -> io::Result<(RenderOptions, usize, usize)> {
+        let (columns, rows) = crossterm::terminal::size()?;
+        let sidebar_width = match columns >= 100 {
+            true => 34,
+            false => 0,
+        };
+        let pane_width = columns as usize - sidebar_width;
+        // A title and a blank line above, status, input and help lines below.
+        let pane_height = (rows as usize).saturating_sub(5);
+        let options = self
+            .display
+            .pane_options(pane_width, pane_height, state.fret_offset);
+        Ok((options, pane_width, pane_height))
+    }
+
+    fn draw_interactive(&self, state: &InteractiveState) -> io::Result<()> {
+        use crossterm::cursor::MoveTo;
+        use crossterm::style::Print;
+        let theme = Theme::current();
+        let (columns, rows) = crossterm::terminal::size()?;
+        let (columns, rows) = (columns as usize, rows as usize);
+        let (options, pane_width, pane_height) = self.interactive_layout(state)?;
+        let sidebar_width = columns - pane_width;
+        let fit = |text: String| text.chars().take(columns).collect::<String>();
+        let mut stdout = io::stdout();
+        crossterm::queue!(
+            stdout,
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
+        )?;
+        let chord = match &self.display.chord {
+            Some(chord) => chord.name.clone(),
+            None => "none".to_string(),
+        };
+        let scale = match &self.display.scale {
+            Some(scale) => scale.name.clone(),
+            None => "none".to_string(),
+        };
+        let notes: Vec<String> = self
+            .display
+            .notes
+            .iter()
+            .map(|note| Theme::printable(&note.get_name()))
+            .collect();
+        let title = format!(
+            "Key: {}   Chord: {}   Scale: {}   Notes: {}",
+            Theme::printable(&self.display.key.get_name()),
+            chord,
+            scale,
+            notes.join(" ")
+        );
+        crossterm::queue!(stdout, MoveTo(0, 0), Print(theme.text(fit(title))))?;
+        let neck = match state.cursor {
+            Some(_) => self
+                .display
+                .fretboard_view(&options)
+                .with_cursor(state.cursor)
+                .to_string(),
+            None => self.display.render(&options),
+        };
+        for (row, line) in neck.lines().take(pane_height).enumerate() {
+            crossterm::queue!(stdout, MoveTo(0, (row + 2) as u16), Print(line))?;
+        }
+        if sidebar_width > 0 {
+            let mut lines = vec!["Instrument Details:".to_string()];
+            lines.extend(self.display.details());
+            lines.push("".to_string());
+            lines.push(format!("{}", self.display.render_options.orientation));
+            lines.push(format!(
+                "Labels: {}",
+                self.display.render_options.label_mode
+            ));
+            lines.push(format!("{}", options.fret_window));
+            if !self.display.notes.is_empty() {
+                let suggestions = &self.display.suggestions;
+                for (heading, names) in [
+                    ("Chords that fit:", &suggestions.chords),
+                    ("Scales that fit:", &suggestions.scales),
+                ] {
+                    lines.push("".to_string());
+                    lines.push(heading.to_string());
+                    lines.extend(
+                        names
+                            .iter()
+                            .take(SUGGESTION_COUNT)
+                            .map(|name| format!("  {}", name)),
+                    );
+                    match names.len() {
+                        0 => lines.push("  none".to_string()),
+                        count if count > SUGGESTION_COUNT => {
+                            lines.push(format!("  and {} more", count - SUGGESTION_COUNT))
                         }
+                        _ => (),
                     }
                 }
-                for value in contains {
-                    if value == false {
-                        works = false;
-                    }
-                }
-                if works == true && scale.definition.name != "Chromatic" {
-                    results.push(scale.clone());
-                }
+            }
+            for (row, line) in lines.iter().take(pane_height).enumerate() {
+                crossterm::queue!(
+                    stdout,
+                    MoveTo(pane_width as u16, (row + 2) as u16),
+                    Print(theme.text(line))
+                )?;
             }
         }
-        println!("Scales that fit are:");
-        for result in results {
-            println!("{}", result);
+        let prompt = match &state.input {
+            Some(input) => format!(":{}", input),
+            None => "".to_string(),
+        };
+        let bottom = [state.status.clone(), prompt, INTERACTIVE_HELP.to_string()];
+        for (offset, line) in bottom.into_iter().enumerate() {
+            let row = (rows + offset).saturating_sub(3) as u16;
+            crossterm::queue!(stdout, MoveTo(0, row), Print(theme.text(fit(line))))?;
         }
+        io::Write::flush(&mut stdout)
     }
 
-    async fn choose_key(&mut self) {
-        println!("Enter a new key (e.g., C, D#, F#):");
-        let mut input: String = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read input");
-        let key: NoteName = NoteName::from_string(input.trim().to_string());
-        self.display.key = key;
-        println!("Key changed to {}", self.display.key);
-    }
-
-    async fn choose_chord(&mut self) {
-        let mut chord: Option<Chord> = None;
-        loop {
-            println!("Enter a chord (e.g., C major seven, D minor, G six):");
-            let mut input: String = String::new();
-            io::stdin()
-                .read_line(&mut input)
-                .expect("Failed to read input");
-            input = input.to_uppercase();
-            let (key_string, input_mod) = Runtime::split_input(input);
-            match key_string.as_str() {
-                "" => (),
-                _ => self.display.key = NoteName::from_string(key_string),
-            };
-            chord = Chord::from_string(&self.display.key, input_mod.trim().to_string());
-            match chord {
-                None => (),
-                Some(ref _chord) => break,
-            };
+    // Returns false when the key asks to leave interactive mode.
+    fn interactive_key(
+        &mut self,
+        key: crossterm::event::KeyEvent,
+        state: &mut InteractiveState,
+    ) -> bool {
+        use crossterm::event::{KeyCode, KeyModifiers};
+        if let Some(input) = &mut state.input {
+            match key.code {
+                KeyCode::Enter => {
+                    let command = input.clone();
+                    state.input = None;
+                    state.status = self.interactive_command(&command);
+                }
+                KeyCode::Esc => state.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(character) => input.push(character),
+                _ => (),
+            }
+            return true;
         }
-        self.display.chord = chord.clone();
-        let chord = chord.unwrap();
-        self.display.notes = chord.notes.clone();
-        self.display.notes.sort();
-        Instrument::show_notes(&mut self.display.instrument, &self.display.notes);
-        println!(
-            "Chord changed to {} {} definition: {}",
-            chord, chord.name, chord.definition
-        );
+        let (first, last) = self
+            .display
+            .render_options
+            .window_frets(&self.display.instrument);
+        let horizontal = self.display.render_options.orientation == Orientation::Horizontal;
+        let left_handed = self.display.render_options.handedness == Handedness::Left;
+        // Arrows move across the strings or along the frets as the neck is drawn.
+        let step = match (key.code, horizontal) {
+            (KeyCode::Left, true) | (KeyCode::Right, true) if left_handed => {
+                Some((0, if key.code == KeyCode::Left { 1 } else { -1 }))
+            }
+            (KeyCode::Left, true) => Some((0, -1)),
+            (KeyCode::Right, true) => Some((0, 1)),
+            (KeyCode::Up, true) | (KeyCode::Left, false) => Some((-1, 0)),
+            (KeyCode::Down, true) | (KeyCode::Right, false) => Some((1, 0)),
+            (KeyCode::Up, false) => Some((0, -1)),
+            (KeyCode::Down, false) => Some((0, 1)),
+            _ => None,
+        };
+        if let Some((across, along)) = step {
+            self.move_cursor(state, across, along);
+            return true;
+        }
+        let display = &mut self.display;
+        state.status = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Esc if state.cursor.is_some() => {
+                state.cursor = None;
+                return true;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char(' ') | KeyCode::Enter => match state.cursor {
+                Some(position) => display.toggle(position),
+                None => {
+                    self.move_cursor(state, 0, 0);
+                    return true;
+                }
+            },
+            // The notes only came from toggles while any are toggled; see refresh.
+            KeyCode::Char('x') if display.toggled.is_empty() => "No notes are toggled".to_string(),
+            KeyCode::Char('x') => {
+                display.toggled.clear();
+                display.notes.clear();
+                display.refresh();
+                "Cleared the toggled notes".to_string()
+            }
+            KeyCode::Char(':') => {
+                state.input = Some(String::new());
+                return true;
+            }
+            KeyCode::Char('k') => display.shift_key(1),
+            KeyCode::Char('K') => display.shift_key(-1),
+            KeyCode::Char('c') => display.cycle_chord(1),
+            KeyCode::Char('C') => display.cycle_chord(-1),
+            KeyCode::Char('s') => display.cycle_scale(1),
+            KeyCode::Char('S') => display.cycle_scale(-1),
+            KeyCode::Char('t') => display.cycle_tuning_type(1),
+            KeyCode::Char('T') => display.cycle_tuning_type(-1),
+            KeyCode::Char('u') => display.transpose_tuning(1),
+            KeyCode::Char('U') => display.transpose_tuning(-1),
+            KeyCode::Char('o') => {
+                display.render_options.orientation = match display.render_options.orientation {
+                    Orientation::Horizontal => Orientation::Vertical,
+                    Orientation::Vertical => Orientation::Horizontal,
+                };
+                format!(
+                    "Orientation changed to {}",
+                    display.render_options.orientation
+                )
+            }
+            KeyCode::Char('l') => {
+                display.render_options.label_mode = display.render_options.label_mode.next();
+                format!("Labels changed to {}", display.render_options.label_mode)
+            }
+            KeyCode::Char('[') => {
+                state.fret_offset = state.fret_offset.saturating_sub(1);
+                return true;
+            }
+            KeyCode::Char(']') => {
+                state.fret_offset = (state.fret_offset + 1).min(last - first);
+                return true;
+            }
+            _ => return true,
+        };
+        true
     }
 
-    async fn choose_scale(&mut self) {
-        let mut scale: Option<Scale> = None;
-        loop {
-            println!("Enter a scale (e.g., E Major, D Minor, G Dorian):");
-            let mut input: String = String::new();
-            io::stdin()
-                .read_line(&mut input)
-                .expect("Failed to read input");
-            input = input.to_uppercase();
-            let (key_string, input_mod) = Runtime::split_input(input);
-            match key_string.as_str() {
-                "" => (),
-                _ => self.display.key = NoteName::from_string(key_string),
-            };
-            scale = Scale::from_string(&self.display.key, input_mod.trim().to_string());
-            match scale {
-                None => (),
-                Some(ref _scale) => break,
-            };
+    // Moves the cursor by across strings and along frets, in drawing order, scrolling the pane to
+    // keep it in view. The first move puts it on the first string at the start of the pane.
+    fn move_cursor(&self, state: &mut InteractiveState, across: isize, along: isize) {
+        let Ok((pane, _, _)) = self.interactive_layout(state) else {
+            return;
+        };
+        let options = &self.display.render_options;
+        let instrument = &self.display.instrument;
+        let strings = options.string_indices(instrument.string_count);
+        let (first, last) = options.window_frets(instrument);
+        let (start, end) = pane.window_frets(instrument);
+        let (string, fret) = state.cursor.unwrap_or((strings[0], start));
+        let index = strings.iter().position(|&s| s == string).unwrap_or(0) as isize;
+        let string = strings[(index + across).clamp(0, strings.len() as isize - 1) as usize];
+        let fret = (fret as isize + along).clamp(first as isize, last as isize) as usize;
+        if fret < start {
+            state.fret_offset -= start - fret;
+        } else if fret > end {
+            state.fret_offset += fret - end;
         }
-        self.display.scale = scale.clone();
-        let scale = scale.unwrap();
-        self.display.notes = scale.notes.clone();
-        self.display.notes.sort();
-        Instrument::show_notes(&mut self.display.instrument, &self.display.notes);
-        println!(
-            "Scale changed to {} {} definition: {}",
-            scale, scale.name, scale.definition
+        state.cursor = Some((string, fret));
+        let pitch = &instrument.fretboard[string][fret].note_pitch;
+        state.status = format!(
+            "String {} fret {}: {}",
+            instrument.string_count - string,
+            fret,
+            Theme::printable(&pitch.get_name())
         );
     }
 
-    async fn change_tuning(&mut self) {
-        println!("Enter a tuning (e.g., E Standard, D Drop, G Open):");
-        let mut input: String = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read input");
-        let (key, input_mod) = Runtime::split_input(input);
-        let tuning_type: TuningType = TuningType::from_string(input_mod.trim().to_string());
-        self.display.instrument.tuning_type = tuning_type;
-        let key = NoteName::from_string(key);
-        self.display.instrument.root_note = NotePitch::from_note_name(&key, 2);
-        self.display.instrument.tuning = Vec::new();
-        self.display.instrument.calculate_tuning();
-        self.display.instrument.calculate_notes();
-        match self.display.notes.len() {
-            0 => self.display.instrument.show_all(),
-            _ => self.display.instrument.show_notes(&self.display.notes),
+    // A left click toggles the position under it and moves the cursor there. A chord box has no
+    // positions to click, so the first click brings up the fretboard with the cursor instead.
+    fn interactive_click(
+        &mut self,
+        column: usize,
+        row: usize,
+        state: &mut InteractiveState,
+    ) -> io::Result<()> {
+        let (options, pane_width, _) = self.interactive_layout(state)?;
+        if column >= pane_width {
+            return Ok(());
         }
-        println!(
-            "Tuning changed to {} {}",
-            self.display.instrument.root_note.note_name, self.display.instrument.tuning_type
-        );
+        if state.cursor.is_none() && self.display.shows_chord_box(&options) {
+            self.move_cursor(state, 0, 0);
+            return Ok(());
+        }
+        let view = self.display.fretboard_view(&options);
+        if let Some(position) = row
+            .checked_sub(2)
+            .and_then(|row| view.position_at(column, row))
+        {
+            state.cursor = Some(position);
+            state.status = self.display.toggle(position);
+        }
+        Ok(())
     }
 
-    async fn show_details(&mut self) {
-        println!("Instrument Details:");
-        println!("\tType: {}", self.display.instrument.instrument_type);
-        println!(
-            "\tNumber of strings: {}",
-            self.display.instrument.string_count
-        );
-        println!("\tNumber of frets: {}", self.display.instrument.fret_count);
-        println!(
-            "\tTuning: {} {}",
-            self.display.instrument.root_note.note_name, self.display.instrument.tuning_type
-        );
-        print!("\tOpen Notes: ");
-        for note in &self.display.instrument.tuning {
-            print!("{} ", note);
+    // Commands typed after ":", with the same names as the menu's choices.
+    fn interactive_command(&mut self, command: &str) -> String {
+        let (name, argument) = command
+            .trim()
+            .split_once(' ')
+            .unwrap_or((command.trim(), ""));
+        match name.to_lowercase().as_str() {
+            "" => String::new(),
+            "key" => match NoteName::parse(argument.trim()) {
+                Some(key) => self.display.set_key(&key),
+                None => format!("{} is not a key", argument.trim()),
+            },
+            "chord" => match Chord::from_name(argument) {
+                Some(chord) => self.display.set_chord(chord),
+                None => format!("{} is not a chord (e.g., G Major)", argument.trim()),
+            },
+            "scale" => match Scale::from_name(argument) {
+                Some(scale) => self.display.set_scale(scale),
+                None => format!("{} is not a scale (e.g., A Dorian)", argument.trim()),
+            },
+            "notes" => {
+                let notes: Option<Vec<NoteName>> = argument
+                    .split(',')
+                    .map(|n| NoteName::parse(n.trim()))
+                    .collect();
+                match notes {
+                    Some(mut notes) => {
+                        notes.sort();
+                        notes.dedup();
+                        self.display.notes = notes;
+                        self.display.refresh();
+                        format!("Notes changed to {}", argument.trim())
+                    }
+                    None => format!("{} are not notes (e.g., C, E, G)", argument.trim()),
+                }
+            }
+            "clear" => {
+                self.display.notes = Vec::new();
+                self.display.chord = None;
+                self.display.voicing = None;
+                self.display.scale = None;
+                self.display.refresh();
+                "Cleared the chord, scale and notes".to_string()
+            }
+            _ => format!(
+                "Unknown command {}, try key, chord, scale, notes or clear",
+                name
+            ),
         }
-        print!("\n");
     }
 
     async fn display_full_instrument(&mut self) {
@@ -2271,3 +9080,503 @@ impl Runtime {
         return words;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pitch(name: &str) -> NotePitch {
+        NotePitch::parse(name).expect("test pitch")
+    }
+
+    fn pitches(names: &[&str]) -> Vec<NotePitch> {
+        names.iter().map(|name| pitch(name)).collect()
+    }
+
+    fn test_engine(channels: u16, sample_rate: u32) -> AudioEngine {
+        AudioEngine {
+            device: None,
+            config: StreamConfig {
+                channels,
+                sample_rate: SampleRate(sample_rate),
+                buffer_size: BufferSize::Default,
+            },
+            settings: SynthSettings::new(),
+        }
+    }
+
+    fn u16_at(bytes: &[u8], index: usize) -> u16 {
+        u16::from_le_bytes([bytes[index], bytes[index + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], index: usize) -> u32 {
+        u32::from_le_bytes([
+            bytes[index],
+            bytes[index + 1],
+            bytes[index + 2],
+            bytes[index + 3],
+        ])
+    }
+
+    #[test]
+    fn write_wav_header() {
+        let engine = test_engine(2, 44100);
+        let path = std::env::temp_dir().join("fretninja-test-header.wav");
+        let path = path.to_str().unwrap();
+        engine.write_wav(path, &[0.0; 6]).unwrap();
+        let bytes = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(bytes.len(), 44 + 12);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32_at(&bytes, 4), 36 + 12);
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(u32_at(&bytes, 16), 16);
+        assert_eq!(u16_at(&bytes, 20), 1);
+        assert_eq!(u16_at(&bytes, 22), 2);
+        assert_eq!(u32_at(&bytes, 24), 44100);
+        assert_eq!(u32_at(&bytes, 28), 44100 * 2 * 2);
+        assert_eq!(u16_at(&bytes, 32), 4);
+        assert_eq!(u16_at(&bytes, 34), 16);
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(u32_at(&bytes, 40), 12);
+    }
+
+    #[test]
+    fn write_wav_clamps_samples() {
+        let engine = test_engine(1, 8000);
+        let path = std::env::temp_dir().join("fretninja-test-clamp.wav");
+        let path = path.to_str().unwrap();
+        engine.write_wav(path, &[2.0, -2.0, 0.5, 0.0]).unwrap();
+        let bytes = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let values: Vec<i16> = bytes[44..]
+            .chunks(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        assert_eq!(values, vec![i16::MAX, -i16::MAX, i16::MAX / 2, 0]);
+    }
+
+    #[test]
+    fn render_lasts_through_the_release() {
+        let engine = test_engine(2, 1000);
+        let events = vec![
+            AudioEvent::new(&[pitch("A4")], 0.5),
+            AudioEvent::new(&[], 0.25),
+        ];
+        let samples = engine.render(&events);
+        // The note's 200 frame release ends inside the rest, so the events set the length.
+        assert_eq!(samples.len(), 750 * 2);
+        assert!((engine.samples_to_secs(samples.len()) - 0.75).abs() < 1e-6);
+        assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+    }
+
+    fn plucked_engine(damping: f32, brightness: f32) -> AudioEngine {
+        let mut engine = test_engine(1, 44100);
+        engine.settings.model = VoiceModel::PluckedString {
+            damping,
+            brightness,
+        };
+        engine
+    }
+
+    #[test]
+    fn plucked_string_is_in_tune() {
+        let samples = plucked_engine(0.5, 0.5).render(&[AudioEvent::new(&[pitch("A4")], 0.5)]);
+        // The lag that best lines the signal up with itself is one period.
+        let window = &samples[4410..8820];
+        let correlation = |lag: usize| -> f32 {
+            window
+                .iter()
+                .zip(&samples[4410 + lag..])
+                .map(|(a, b)| a * b)
+                .sum()
+        };
+        let period = (50..200)
+            .max_by(|&a, &b| correlation(a).total_cmp(&correlation(b)))
+            .unwrap();
+        let frequency = 44100.0 / period as f32;
+        assert!((frequency - 440.0).abs() < 4.4, "{}", frequency);
+    }
+
+    #[test]
+    fn plucked_string_extremes_stay_audible_and_bounded() {
+        for (damping, brightness) in [(0.0, 0.0), (0.0, 1.0), (1.0, 0.0), (1.0, 1.0)] {
+            let samples =
+                plucked_engine(damping, brightness).render(&[AudioEvent::new(&[pitch("A4")], 0.5)]);
+            assert!(samples
+                .iter()
+                .all(|sample| sample.is_finite() && sample.abs() <= 1.0));
+            let early = &samples[441..4410];
+            let rms = (early.iter().map(|sample| sample * sample).sum::<f32>()
+                / early.len() as f32)
+                .sqrt();
+            assert!(rms > 0.01, "{} {} {}", damping, brightness, rms);
+        }
+    }
+
+    #[test]
+    fn scale_patterns_start_on_the_root() {
+        let guitar = Instrument::standard(&InstrumentType::Guitar);
+        let a_minor = Scale::new(
+            &pitch("A4").note_name,
+            &ScaleDefinition::new_natural_minor(),
+        );
+        let patterns = guitar.scale_patterns(&a_minor, &PatternSystem::ThreeNotesPerString);
+        assert_eq!(patterns.len(), 7);
+        let low_string: Vec<usize> = patterns[0]
+            .notes
+            .iter()
+            .filter(|&&(i, _, _)| i == 0)
+            .map(|&(_, fret, _)| fret)
+            .collect();
+        assert_eq!(low_string, vec![5, 7, 8]);
+        let empty = Scale {
+            notes: Vec::new(),
+            ..a_minor
+        };
+        assert!(guitar
+            .scale_patterns(&empty, &PatternSystem::OneOctave)
+            .is_empty());
+    }
+
+    #[test]
+    fn user_layers_outlive_show_calls() {
+        let mut guitar = Instrument::standard(&InstrumentType::Guitar);
+        let target = guitar.find_pitch(&pitch("A4"));
+        let (string, fret) = target[0];
+        guitar.add_layer(HighlightLayer::new("target", &HighlightStyle::Bold, target));
+        guitar.show_notes(&[pitch("C4").note_name]);
+        guitar.show_all();
+        let layer = guitar.top_layer(string, fret).expect("A4 is highlighted");
+        assert_eq!(layer.name, "target");
+        assert!(guitar.remove_layer("TARGET"));
+        assert_eq!(guitar.top_layer(string, fret).unwrap().name, "all");
+    }
+
+    #[test]
+    fn fret_table_positions() {
+        let table = FretTable {
+            scale_length: ScaleLength::Single(648.0),
+            temperament: Temperament::Equal,
+            string_count: 6,
+            fret_count: 24,
+        };
+        assert_eq!(table.nut_distance(0, 0), 0.0);
+        assert!((table.nut_distance(0, 12) - 324.0).abs() < 1e-9);
+        assert!((table.bridge_distance(5, 24) - 162.0).abs() < 1e-9);
+        assert!((table.nut_distance(0, 1) - 36.369).abs() < 1e-3);
+    }
+
+    #[test]
+    fn fret_table_multiscale_strings() {
+        let table = FretTable {
+            scale_length: ScaleLength::Multiscale {
+                bass: 686.0,
+                treble: 648.0,
+                perpendicular_fret: 7,
+            },
+            temperament: Temperament::Just,
+            string_count: 7,
+            fret_count: 24,
+        };
+        assert_eq!(table.string_length(0), 686.0);
+        assert_eq!(table.string_length(6), 648.0);
+        assert!((table.string_length(3) - 667.0).abs() < 1e-9);
+        // A just fifth sits two thirds of the way to the bridge.
+        assert!((table.bridge_distance(6, 7) - 432.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn string_gauge_tension() {
+        let plain = StringGauge::from_string("PL010".to_string());
+        let tension = plain.tension(&pitch("E4"), 25.5 * MM_PER_INCH);
+        assert!((tension - 16.2).abs() < 0.1, "{}", tension);
+        let wound = StringGauge::from_string("NW046".to_string());
+        let tension = wound.tension(&pitch("E2"), 25.5 * MM_PER_INCH);
+        assert!((tension - 17.5).abs() < 0.5, "{}", tension);
+        // Tension grows with the square of the frequency.
+        let octave_up = plain.tension(&pitch("E5"), 25.5 * MM_PER_INCH);
+        assert!((octave_up / plain.tension(&pitch("E4"), 25.5 * MM_PER_INCH) - 4.0).abs() < 1e-9);
+        let mut guitar = Instrument::standard(&InstrumentType::Guitar);
+        assert!(guitar
+            .string_tensions()
+            .iter()
+            .all(|&(_, status)| status == TensionStatus::Ok));
+        assert!(!guitar.tension_summary().contains("ok"));
+        guitar.transpose(-12);
+        let summary = guitar.tension_summary();
+        assert_eq!(summary.matches(", ").count(), 5, "{}", summary);
+        assert_eq!(summary.matches("too slack").count(), 6, "{}", summary);
+    }
+
+    #[test]
+    fn harmonic_nodes() {
+        let guitar = Instrument::standard(&InstrumentType::Guitar);
+        let harmonics = guitar.harmonics();
+        let nodes = |partial: u8| -> Vec<&Harmonic> {
+            harmonics
+                .iter()
+                .filter(|harmonic| harmonic.string == 0 && harmonic.partial == partial)
+                .collect()
+        };
+        let octave = nodes(2);
+        assert_eq!(octave.len(), 1);
+        assert!((octave[0].fret - 12.0).abs() < 1e-9);
+        assert_eq!(octave[0].pitch, pitch("E3"));
+        let fifth: Vec<f64> = nodes(3).iter().map(|harmonic| harmonic.fret).collect();
+        assert_eq!(fifth.len(), 2);
+        assert!((fifth[0] - 7.02).abs() < 0.01 && (fifth[1] - 19.02).abs() < 0.01);
+        assert_eq!(nodes(3)[0].pitch, pitch("B3"));
+        assert!((nodes(3)[0].cents - 1.96).abs() < 0.01);
+        // The middle node of the fourth partial is the octave's, and 24 is the last fret.
+        let fourth: Vec<usize> = nodes(4).iter().map(|h| h.nearest_fret()).collect();
+        assert_eq!(fourth, vec![5, 24]);
+        assert!(harmonics.iter().all(|harmonic| harmonic.fret <= 24.0));
+    }
+
+    fn test_session() -> Session {
+        let mandolin = Instrument::standard(&InstrumentType::Mandolin);
+        Session {
+            key: NoteName::parse("F#").unwrap(),
+            notes: vec![
+                NoteName::parse("F#").unwrap(),
+                NoteName::parse("A").unwrap(),
+            ],
+            chord: Some("F# Minor".to_string()),
+            scale: None,
+            progression: vec!["F# Minor".to_string(), "D Major".to_string()],
+            theme: Theme::Colorblind,
+            render_options: RenderOptions::new(),
+            instrument: InstrumentDefinition::from_instrument(&mandolin),
+        }
+    }
+
+    #[test]
+    fn session_toml_round_trip() {
+        let session = test_session();
+        let contents = toml::to_string_pretty(&session).unwrap();
+        let loaded: Session = toml::from_str(&contents).unwrap();
+        assert_eq!(loaded, session);
+        let instrument = loaded.instrument.to_instrument().unwrap();
+        assert_eq!(instrument.tuning, pitches(&["G3", "D4", "A4", "E5"]));
+    }
+
+    #[test]
+    fn session_missing_fields_load_with_defaults() {
+        let session = test_session();
+        let mut value = toml::Value::try_from(&session).unwrap();
+        let table = value.as_table_mut().unwrap();
+        for field in ["notes", "chord", "progression", "theme", "render_options"] {
+            table.remove(field);
+        }
+        let loaded: Session = toml::from_str(&toml::to_string(&value).unwrap()).unwrap();
+        assert!(loaded.notes.is_empty() && loaded.progression.is_empty());
+        assert_eq!(loaded.chord, None);
+        assert_eq!(loaded.render_options, RenderOptions::new());
+    }
+
+    #[test]
+    fn empty_tuning_is_rejected() {
+        let mut definition = test_session().instrument;
+        definition.tuning.clear();
+        assert!(definition.to_instrument().is_err());
+    }
+
+    #[test]
+    fn fret_count_is_checked() {
+        let mut definition = test_session().instrument;
+        for (fret_count, valid) in [(0, false), (1, true), (36, true), (37, false), (200, false)] {
+            definition.fret_count = fret_count;
+            assert_eq!(definition.to_instrument().is_ok(), valid, "{}", fret_count);
+        }
+    }
+
+    fn test_tab() -> Tab {
+        let guitar = Instrument::standard(&InstrumentType::Guitar);
+        let mut tab = Tab::new(&guitar.tuning);
+        tab.push_chord(&[None, Some(3), Some(2), Some(0), Some(1), Some(0)]);
+        tab.push_bar();
+        tab.push_chord(&[Some(3), Some(2), Some(0), Some(0), Some(0), Some(3)]);
+        tab.push_bar();
+        tab
+    }
+
+    #[test]
+    fn tab_render() {
+        let expected = "E|-0-|-3-|\nB|-1-|-0-|\nG|-0-|-0-|\nD|-2-|-0-|\nA|-3-|-2-|\nE|---|-3-|\n";
+        assert_eq!(test_tab().render(80), expected);
+    }
+
+    #[test]
+    fn tab_render_wraps_and_drops_edge_bars() {
+        let expected = "E|-0-|\nB|-1-|\nG|-0-|\nD|-2-|\nA|-3-|\nE|---|\n\n\
+                        E|-3-|\nB|-0-|\nG|-0-|\nD|-0-|\nA|-2-|\nE|-3-|\n";
+        assert_eq!(test_tab().render(7), expected);
+    }
+
+    #[test]
+    fn tab_render_pads_double_digit_frets() {
+        let mut tab = Tab::new(&pitches(&["E2", "A2"]));
+        tab.push_run(&[(0, 12), (1, 3)], &RunDirection::Up);
+        assert_eq!(tab.render(80), "A|----3-|\nE|-12---|\n");
+    }
+
+    #[test]
+    fn tab_push_run_skips_an_empty_run() {
+        let mut tab = Tab::new(&pitches(&["E2", "A2"]));
+        for direction in [
+            RunDirection::Up,
+            RunDirection::Down,
+            RunDirection::UpAndDown,
+        ] {
+            tab.push_run(&[], &direction);
+        }
+        assert!(tab.columns.is_empty());
+    }
+
+    #[test]
+    fn envelope_stages() {
+        let envelope = Envelope {
+            attack: 0.1,
+            decay: 0.2,
+            sustain: 0.5,
+            release: 0.4,
+        };
+        let held = 1.0;
+        assert_eq!(envelope.level(0.0, held), 0.0);
+        assert!((envelope.level(0.05, held) - 0.5).abs() < 1e-6);
+        assert!((envelope.level(0.2, held) - 0.75).abs() < 1e-6);
+        assert_eq!(envelope.level(0.5, held), 0.5);
+        assert!((envelope.level(1.2, held) - 0.25).abs() < 1e-6);
+        assert_eq!(envelope.level(1.5, held), 0.0);
+        // Released during the attack, the release starts from wherever the attack had reached.
+        assert!((envelope.level(0.25, 0.05) - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn envelope_zero_length_stages() {
+        let envelope = Envelope {
+            attack: 0.0,
+            decay: 0.0,
+            sustain: 0.6,
+            release: 0.0,
+        };
+        assert_eq!(envelope.level(0.0, 1.0), 0.6);
+        assert_eq!(envelope.level(0.5, 1.0), 0.6);
+        assert_eq!(envelope.level(1.0, 1.0), 0.0);
+        let envelope = Envelope {
+            attack: 0.0,
+            decay: 0.2,
+            sustain: 0.6,
+            release: 0.1,
+        };
+        assert_eq!(envelope.level(0.0, 1.0), 1.0);
+        assert!((0..200).all(|ms| envelope.level(ms as f32 / 100.0, 1.0).is_finite()));
+    }
+
+    #[test]
+    fn arpeggio_pattern_from_string() {
+        assert_eq!(
+            ArpeggioPattern::from_string("up".to_string()),
+            ArpeggioPattern::Run(RunDirection::Up)
+        );
+        assert_eq!(
+            ArpeggioPattern::from_string("BOTH".to_string()),
+            ArpeggioPattern::Run(RunDirection::UpAndDown)
+        );
+        assert_eq!(
+            ArpeggioPattern::from_string("1 3 2 4".to_string()),
+            ArpeggioPattern::Tones(vec![Some(0), Some(2), Some(1), Some(3)])
+        );
+        assert_eq!(
+            ArpeggioPattern::from_string("1,.,5".to_string()),
+            ArpeggioPattern::Tones(vec![Some(0), None, Some(4)])
+        );
+    }
+
+    #[test]
+    fn arpeggio_pattern_events() {
+        let chord = pitches(&["C4", "E4", "G4"]);
+        let frequencies = |events: Vec<AudioEvent>| -> Vec<Option<f32>> {
+            events
+                .iter()
+                .map(|event| event.notes.first().map(|note| note.frequency))
+                .collect()
+        };
+        let expected = |names: &[Option<&str>]| -> Vec<Option<f32>> {
+            names
+                .iter()
+                .map(|name| name.map(|name| pitch(name).frequency() as f32))
+                .collect()
+        };
+        let tones = ArpeggioPattern::Tones(vec![Some(0), Some(2), None, Some(3)]);
+        let events = tones.events(&chord, 0.25);
+        assert!(events.iter().all(|event| event.duration_secs == 0.25));
+        assert_eq!(
+            frequencies(events),
+            expected(&[Some("C4"), Some("G4"), None, Some("C5")])
+        );
+        let run = ArpeggioPattern::Run(RunDirection::UpAndDown);
+        assert_eq!(
+            frequencies(run.events(&chord, 0.25)),
+            expected(&[Some("C4"), Some("E4"), Some("G4"), Some("E4"), Some("C4")])
+        );
+    }
+
+    #[test]
+    fn scale_sequence_apply() {
+        let run = pitches(&["C4", "D4", "E4", "F4", "G4"]);
+        assert_eq!(ScaleSequence::Straight.apply(&run), run);
+        assert_eq!(
+            ScaleSequence::Intervals(2).apply(&run),
+            pitches(&["C4", "E4", "D4", "F4", "E4", "G4"])
+        );
+        assert_eq!(
+            ScaleSequence::Groups(3).apply(&run),
+            pitches(&["C4", "D4", "E4", "D4", "E4", "F4", "E4", "F4", "G4"])
+        );
+        assert!(ScaleSequence::Groups(6).apply(&run).is_empty());
+    }
+
+    #[test]
+    fn tap_tempo() {
+        let start = Instant::now();
+        let taps = |gaps_ms: &[u64]| -> Vec<Instant> {
+            gaps_ms
+                .iter()
+                .map(|&ms| start + Duration::from_millis(ms))
+                .collect()
+        };
+        assert_eq!(Metronome::tap_tempo(&[]), None);
+        assert_eq!(Metronome::tap_tempo(&taps(&[0])), None);
+        assert_eq!(
+            Metronome::tap_tempo(&taps(&[0, 500, 1000, 1500])),
+            Some(120.0)
+        );
+        assert_eq!(Metronome::tap_tempo(&taps(&[0, 100, 200])), Some(300.0));
+        // Only the last eight taps count, so an early slow tap is forgotten.
+        let mut late = taps(&[0]);
+        late.extend(taps(&[5000, 5750, 6500, 7250, 8000, 8750, 9500, 10250]));
+        assert_eq!(Metronome::tap_tempo(&late), Some(80.0));
+    }
+
+    #[test]
+    fn metronome_bar_counts_beats_of_the_unit() {
+        let mut metronome = Metronome::new();
+        metronome.time_signature = TimeSignature { beats: 6, unit: 8 };
+        metronome.accents = Accent::default_pattern(6);
+        let bar = metronome.bar();
+        assert_eq!(bar.len(), 6);
+        assert!(bar
+            .iter()
+            .all(|event| (event.duration_secs - 0.5).abs() < 1e-6));
+        metronome.subdivisions = 2;
+        let bar = metronome.bar();
+        assert_eq!(bar.len(), 12);
+        assert!(bar
+            .iter()
+            .all(|event| (event.duration_secs - 0.25).abs() < 1e-6));
+        assert_eq!(bar[0].notes[0].frequency, METRONOME_ACCENT_HZ);
+        assert!(bar[1].notes[0].gain < bar[2].notes[0].gain);
+    }
+}